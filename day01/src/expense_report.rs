@@ -0,0 +1,220 @@
+use crate::k_sum::find_k_sum;
+use crate::objective::{select_best, ArithmeticOverflow, Match, Objective};
+use crate::subset_sum::{find_closest_subset, find_subset_sum, SubsetSumError};
+use std::convert::TryFrom;
+use std::fmt;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+/// How to treat lines that can't be parsed as an entry.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParseMode {
+    /// Fail if any line is invalid.
+    Strict,
+    /// Skip invalid lines, but report them as diagnostics.
+    Lenient,
+}
+
+/// A single line of the expense report that couldn't be parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntryParseError {
+    /// 1-based line number in the input.
+    pub line: usize,
+    pub raw: String,
+    pub cause: ParseIntError,
+}
+
+impl fmt::Display for EntryParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {:?}: {}", self.line, self.raw, self.cause)
+    }
+}
+
+/// All lines of the expense report that couldn't be parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpenseReportParseError {
+    pub errors: Vec<EntryParseError>,
+}
+
+impl fmt::Display for ExpenseReportParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} invalid expense report entries", self.errors.len())?;
+        for error in &self.errors {
+            write!(f, "\n  {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ExpenseReportParseError {}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ExpenseReport {
+    entries: Vec<i32>,
+}
+
+impl ExpenseReport {
+    pub fn new(entries: Vec<i32>) -> ExpenseReport {
+        ExpenseReport { entries }
+    }
+
+    /// Parses one entry per line. Blank lines are ignored.
+    ///
+    /// Returns the report together with the errors of all invalid lines.
+    /// In strict mode, any invalid line makes the whole parse fail.
+    pub fn parse(
+        report_str: &str,
+        mode: ParseMode,
+    ) -> Result<(ExpenseReport, Vec<EntryParseError>), ExpenseReportParseError> {
+        let mut entries = Vec::new();
+        let mut errors = Vec::new();
+        for (line_id, line) in report_str.lines().enumerate() {
+            let raw = line.trim();
+            if raw.is_empty() {
+                continue;
+            }
+            match raw.parse::<i32>() {
+                Ok(entry) => entries.push(entry),
+                Err(cause) => errors.push(EntryParseError {
+                    line: line_id + 1,
+                    raw: raw.to_string(),
+                    cause,
+                }),
+            }
+        }
+
+        if mode == ParseMode::Strict && !errors.is_empty() {
+            return Err(ExpenseReportParseError { errors });
+        }
+        Ok((ExpenseReport { entries }, errors))
+    }
+
+    /// Parses the report, skipping invalid lines. The skipped lines are returned as diagnostics.
+    pub fn parse_lenient(report_str: &str) -> (ExpenseReport, Vec<EntryParseError>) {
+        ExpenseReport::parse(report_str, ParseMode::Lenient).expect("lenient parsing never fails")
+    }
+
+    pub fn entries(&self) -> &[i32] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// See `k_sum::find_k_sum`.
+    pub fn find_k_sum(&self, k: usize, target: i32) -> Vec<Vec<i32>> {
+        find_k_sum(&self.entries, k, target)
+    }
+
+    /// Like `find_k_sum`, but with the widened sum and checked product of every match.
+    pub fn find_k_sum_matches(&self, k: usize, target: i32) -> Vec<Match> {
+        self.find_k_sum(k, target)
+            .into_iter()
+            .map(Match::new)
+            .collect()
+    }
+
+    /// The k-sum match that optimizes the objective, see `objective::select_best`.
+    pub fn find_best_k_sum(
+        &self,
+        k: usize,
+        target: i32,
+        objective: Objective,
+    ) -> Result<Option<Match>, ArithmeticOverflow> {
+        let matches = self.find_k_sum_matches(k, target);
+        Ok(select_best(&matches, objective)?.cloned())
+    }
+
+    /// See `subset_sum::find_subset_sum`.
+    pub fn find_subset_sum(&self, target: i32) -> Result<Option<Vec<i32>>, SubsetSumError> {
+        Ok(find_subset_sum(&self.entries, target)?.map(|indices| self.values_at(&indices)))
+    }
+
+    /// See `subset_sum::find_closest_subset`.
+    pub fn find_closest_subset(
+        &self,
+        target: i32,
+        max_entries: usize,
+    ) -> Result<Vec<i32>, SubsetSumError> {
+        Ok(self.values_at(&find_closest_subset(&self.entries, target, max_entries)?))
+    }
+
+    fn values_at(&self, indices: &[usize]) -> Vec<i32> {
+        indices.iter().map(|&id| self.entries[id]).collect()
+    }
+}
+
+impl FromStr for ExpenseReport {
+    type Err = ExpenseReportParseError;
+
+    /// Strict parsing, fails on any invalid line.
+    fn from_str(report_str: &str) -> Result<Self, Self::Err> {
+        ExpenseReport::parse(report_str, ParseMode::Strict).map(|(report, _)| report)
+    }
+}
+
+impl TryFrom<&str> for ExpenseReport {
+    type Error = ExpenseReportParseError;
+
+    fn try_from(report_str: &str) -> Result<Self, Self::Error> {
+        report_str.parse()
+    }
+}
+
+#[test]
+fn test_parse_strict() {
+    let report: ExpenseReport = "1721\n979\n366\n299\n675\n1456\n".parse().unwrap();
+    assert_eq!(report.entries(), [1721, 979, 366, 299, 675, 1456]);
+    assert_eq!(
+        ExpenseReport::try_from("-3\r\n\n  12 \n").unwrap(),
+        ExpenseReport::new(vec![-3, 12])
+    );
+
+    let error = "1721\nabc\n366\n99999999999\n"
+        .parse::<ExpenseReport>()
+        .unwrap_err();
+    assert_eq!(error.errors.len(), 2);
+    assert_eq!(error.errors[0].line, 2);
+    assert_eq!(error.errors[0].raw, "abc");
+    assert_eq!(error.errors[1].line, 4);
+    assert_eq!(error.errors[1].raw, "99999999999");
+}
+
+#[test]
+fn test_parse_lenient() {
+    let (report, errors) = ExpenseReport::parse_lenient("1721\n979x\n\n366\n");
+    assert_eq!(report.entries(), [1721, 366]);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].line, 2);
+    assert_eq!(errors[0].raw, "979x");
+    assert_eq!(
+        errors[0].to_string(),
+        "line 2: \"979x\": invalid digit found in string"
+    );
+}
+
+#[test]
+fn test_subset_solvers() {
+    let report = ExpenseReport::new(vec![1721, 979, 366, 299, 675, 1456]);
+    assert_eq!(report.find_subset_sum(1000).unwrap(), None);
+    assert_eq!(report.find_subset_sum(665).unwrap(), Some(vec![366, 299]));
+    assert_eq!(report.find_closest_subset(2000, 1).unwrap(), vec![1721]);
+}
+
+#[test]
+fn test_find_best_k_sum() {
+    let report = ExpenseReport::new(vec![1, 2, 3, 4, 5, 6]);
+    let best = |objective| report.find_best_k_sum(2, 7, objective).unwrap().unwrap();
+    assert_eq!(best(Objective::MaxProduct).entries, [3, 4]);
+    assert_eq!(best(Objective::MinProduct).entries, [1, 6]);
+    assert_eq!(best(Objective::MaxProduct).product, Ok(12));
+    assert_eq!(
+        report.find_best_k_sum(2, 100, Objective::MaxProduct),
+        Ok(None)
+    );
+}
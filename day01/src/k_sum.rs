@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+
+/// Finds every distinct combination of `k` entries that sums up to `target`.
+///
+/// Each combination is returned exactly once, with its values sorted ascending.
+/// Use `find_k_sum_indices` to also get the positions of the entries in the input.
+pub fn find_k_sum(entries: &[i32], k: usize, target: i32) -> Vec<Vec<i32>> {
+    find_k_sum_indices(entries, k, target)
+        .into_iter()
+        .map(|indices| indices.iter().map(|&id| entries[id]).collect())
+        .collect()
+}
+
+/// Like `find_k_sum`, but returns the indices into `entries` of each combination.
+///
+/// For entries that occur more than once, the first occurrences are used.
+/// The indices of each combination are sorted by the value they point to.
+pub fn find_k_sum_indices(entries: &[i32], k: usize, target: i32) -> Vec<Vec<usize>> {
+    match k {
+        0 => {
+            if target == 0 {
+                vec![Vec::new()]
+            } else {
+                Vec::new()
+            }
+        }
+        1 => find_single(entries, target),
+        2 => find_pairs_hashed(entries, target),
+        _ => {
+            // sort once, then fix the smallest entries until only a pair is left
+            let mut sorted_ids: Vec<usize> = (0..entries.len()).collect();
+            sorted_ids.sort_by_key(|&id| (entries[id], id));
+            let mut combinations = Vec::new();
+            let mut prefix = Vec::with_capacity(k);
+            find_sorted(
+                entries,
+                &sorted_ids,
+                k,
+                target as i64,
+                &mut prefix,
+                &mut combinations,
+            );
+            combinations
+        }
+    }
+}
+
+fn find_single(entries: &[i32], target: i32) -> Vec<Vec<usize>> {
+    match entries.iter().position(|&entry| entry == target) {
+        Some(id) => vec![vec![id]],
+        None => Vec::new(),
+    }
+}
+
+/// Hash based pair search, O(n).
+fn find_pairs_hashed(entries: &[i32], target: i32) -> Vec<Vec<usize>> {
+    // value -> (first index, second index if the value occurs more than once)
+    let mut occurrences = HashMap::<i32, (usize, Option<usize>)>::new();
+    for (id, &entry) in entries.iter().enumerate() {
+        occurrences
+            .entry(entry)
+            .and_modify(|ids| {
+                if ids.1.is_none() {
+                    ids.1 = Some(id)
+                }
+            })
+            .or_insert((id, None));
+    }
+
+    let mut values: Vec<i32> = occurrences.keys().cloned().collect();
+    values.sort_unstable();
+
+    let mut pairs = Vec::new();
+    for value in values {
+        let complement = target as i64 - value as i64;
+        if complement < value as i64 {
+            break; // every pair has already been reported with its smaller value
+        }
+        let complement = complement as i32;
+        let (value_id, second_value_id) = occurrences[&value];
+        if complement == value {
+            if let Some(second_value_id) = second_value_id {
+                pairs.push(vec![value_id, second_value_id]);
+            }
+        } else if let Some(&(complement_id, _)) = occurrences.get(&complement) {
+            pairs.push(vec![value_id, complement_id]);
+        }
+    }
+    pairs
+}
+
+/// Recursive search on the sorted entries, ending in a two-pointer scan. O(n^(k-1)).
+fn find_sorted(
+    entries: &[i32],
+    sorted_ids: &[usize],
+    k: usize,
+    target: i64,
+    prefix: &mut Vec<usize>,
+    combinations: &mut Vec<Vec<usize>>,
+) {
+    if sorted_ids.len() < k {
+        return;
+    }
+    let value = |position: usize| entries[sorted_ids[position]] as i64;
+
+    if k == 2 {
+        let (mut low, mut high) = (0, sorted_ids.len() - 1);
+        while low < high {
+            let sum = value(low) + value(high);
+            if sum < target {
+                low += 1;
+            } else if sum > target {
+                high -= 1;
+            } else {
+                let mut combination = prefix.clone();
+                combination.push(sorted_ids[low]);
+                combination.push(sorted_ids[high]);
+                combinations.push(combination);
+                // skip duplicates, so that each combination is only reported once
+                let (low_value, high_value) = (value(low), value(high));
+                while low < high && value(low) == low_value {
+                    low += 1;
+                }
+                while low < high && value(high) == high_value {
+                    high -= 1;
+                }
+            }
+        }
+        return;
+    }
+
+    for first in 0..=sorted_ids.len() - k {
+        if first > 0 && value(first) == value(first - 1) {
+            continue; // same value as before, would only find the same combinations again
+        }
+        // prune: the smallest possible sum is already too large, or the largest too small
+        let smallest: i64 = (first..first + k).map(value).sum();
+        if smallest > target {
+            break;
+        }
+        let largest: i64 = value(first)
+            + (sorted_ids.len() - (k - 1)..sorted_ids.len())
+                .map(value)
+                .sum::<i64>();
+        if largest < target {
+            continue;
+        }
+
+        prefix.push(sorted_ids[first]);
+        find_sorted(
+            entries,
+            &sorted_ids[first + 1..],
+            k - 1,
+            target - value(first),
+            prefix,
+            combinations,
+        );
+        prefix.pop();
+    }
+}
+
+#[test]
+fn test_k_sum_example() {
+    let expense_report = [1721, 979, 366, 299, 675, 1456];
+    assert_eq!(find_k_sum(&expense_report, 2, 2020), [[299, 1721]]);
+    assert_eq!(find_k_sum(&expense_report, 3, 2020), [[366, 675, 979]]);
+    assert_eq!(find_k_sum_indices(&expense_report, 2, 2020), [[3, 0]]);
+    assert_eq!(find_k_sum_indices(&expense_report, 3, 2020), [[2, 4, 1]]);
+}
+
+#[test]
+fn test_k_sum_reports_each_combination_once() {
+    let entries = [1010, 1010, 1010, 5, 2015, 5];
+    assert_eq!(find_k_sum(&entries, 2, 2020), [[5, 2015], [1010, 1010]]);
+    assert_eq!(find_k_sum_indices(&entries, 2, 2020), [[3, 4], [0, 1]]);
+
+    let entries = [1, 1, 1, 1, 2, 2, 3];
+    assert_eq!(find_k_sum(&entries, 3, 4), [[1, 1, 2]]);
+    assert_eq!(find_k_sum(&entries, 4, 6), [[1, 1, 1, 3], [1, 1, 2, 2]]);
+}
+
+#[test]
+fn test_k_sum_edge_cases() {
+    let entries = [-3, 0, 3, 6, -6];
+    assert_eq!(find_k_sum(&entries, 0, 0), [[]]);
+    assert!(find_k_sum(&entries, 0, 1).is_empty());
+    assert_eq!(find_k_sum(&entries, 1, 6), [[6]]);
+    assert_eq!(find_k_sum(&entries, 2, 0), [[-6, 6], [-3, 3]]);
+    assert_eq!(find_k_sum(&entries, 3, 0), [[-6, 0, 6], [-3, 0, 3]]);
+    assert!(find_k_sum(&entries, 6, 0).is_empty());
+    assert!(find_k_sum(&[i32::MAX, i32::MAX], 2, -2).is_empty());
+}
+
+#[test]
+fn test_k_sum_matches_brute_force() {
+    let entries: Vec<i32> = (0..40).map(|i| (i * 37 % 23) - 7).collect();
+    for &target in &[-5, 0, 3, 17] {
+        let mut expected = Vec::new();
+        for a in 0..entries.len() {
+            for b in a + 1..entries.len() {
+                for c in b + 1..entries.len() {
+                    if entries[a] + entries[b] + entries[c] == target {
+                        let mut combination = vec![entries[a], entries[b], entries[c]];
+                        combination.sort_unstable();
+                        expected.push(combination);
+                    }
+                }
+            }
+        }
+        expected.sort();
+        expected.dedup();
+        let mut found = find_k_sum(&entries, 3, target);
+        found.sort();
+        assert_eq!(found, expected);
+    }
+}
@@ -0,0 +1,5 @@
+pub mod expense_report;
+pub mod k_sum;
+pub mod objective;
+pub mod streaming;
+pub mod subset_sum;
@@ -1,39 +1,158 @@
-use std::fs::read_to_string;
+use day01::expense_report::{ExpenseReport, ParseMode};
+use day01::objective::{Match, Objective};
+use day01::streaming::{find_sums_in_reader, StreamingConfig};
+use std::env;
+use std::fs::{read_to_string, File};
+use std::io;
+
+fn format_entries(entries: &[i32], separator: &str) -> String {
+    entries
+        .iter()
+        .map(|entry| entry.to_string())
+        .collect::<Vec<String>>()
+        .join(separator)
+}
+
+fn print_match(found: &Match) {
+    println!(
+        "Found valid entry {}-tuple {} = {}",
+        found.entries.len(),
+        found.sum,
+        format_entries(&found.entries, " + ")
+    );
+    match &found.product {
+        Ok(product) => println!("{} = {}", format_entries(&found.entries, " * "), product),
+        Err(overflow) => println!("{}", overflow),
+    }
+}
+
+fn print_k_sums(expense_report: &ExpenseReport) {
+    for k in 2..=3 {
+        for found in expense_report.find_k_sum_matches(k, 2020) {
+            print_match(&found);
+        }
+    }
+}
+
+/// Usage: `day01 ksum <k> <target> [max-product|min-product|max-squares|min-squares]`
+fn print_best_k_sum(expense_report: &ExpenseReport, args: &[String]) {
+    let k = args
+        .first()
+        .map(|k| k.parse::<usize>().expect("invalid k"))
+        .unwrap_or(3);
+    let target = args
+        .get(1)
+        .map(|target| target.parse::<i32>().expect("invalid target"))
+        .unwrap_or(2020);
+    let objective = match args.get(2).map(|objective| objective.as_str()) {
+        None => {
+            for found in expense_report.find_k_sum_matches(k, target) {
+                print_match(&found);
+            }
+            return;
+        }
+        Some("max-product") => Objective::MaxProduct,
+        Some("min-product") => Objective::MinProduct,
+        Some("max-squares") => Objective::MaxSumOfSquares,
+        Some("min-squares") => Objective::MinSumOfSquares,
+        Some(objective) => panic!("unknown objective {}", objective),
+    };
+
+    match expense_report.find_best_k_sum(k, target, objective) {
+        Ok(Some(found)) => print_match(&found),
+        Ok(None) => println!("No {}-tuple sums up to {}", k, target),
+        Err(overflow) => println!("Can't select the best {}-tuple: {}", k, overflow),
+    }
+}
+
+/// Usage: `day01 subset <target> [max_entries]`
+fn print_subset(expense_report: &ExpenseReport, args: &[String]) {
+    let target = args
+        .first()
+        .map(|target| target.parse::<i32>().expect("invalid target"))
+        .unwrap_or(2020);
+    let max_entries = args
+        .get(1)
+        .map(|max_entries| max_entries.parse::<usize>().expect("invalid max_entries"));
+
+    match max_entries {
+        None => match expense_report.find_subset_sum(target) {
+            Ok(Some(entries)) => println!(
+                "Found subset {} = {}",
+                target,
+                format_entries(&entries, " + ")
+            ),
+            Ok(None) => println!("No subset sums up to {}", target),
+            Err(e) => println!("Can't search subsets: {}", e),
+        },
+        Some(max_entries) => match expense_report.find_closest_subset(target, max_entries) {
+            Ok(entries) => println!(
+                "Closest subset of at most {} entries to {}: {} = {}",
+                max_entries,
+                target,
+                format_entries(&entries, " + "),
+                entries.iter().map(|&entry| entry as i64).sum::<i64>()
+            ),
+            Err(e) => println!("Can't search subsets: {}", e),
+        },
+    }
+}
+
+/// Usage: `day01 stream <k> <target> [memory_budget]`
+///
+/// Doesn't load the whole input, matches are printed while reading it.
+fn print_streamed_sums(args: &[String]) {
+    let k = args
+        .first()
+        .map(|k| k.parse::<usize>().expect("invalid k"))
+        .unwrap_or(3);
+    let target = args
+        .get(1)
+        .map(|target| target.parse::<i32>().expect("invalid target"))
+        .unwrap_or(2020);
+    let mut config = StreamingConfig::default();
+    if let Some(memory_budget) = args.get(2) {
+        config.memory_budget = memory_budget.parse().expect("invalid memory_budget");
+    }
+
+    let reader = io::BufReader::new(File::open("input.txt").unwrap());
+    let result = find_sums_in_reader(reader, k, target, ParseMode::Lenient, config, |found| {
+        print_match(&Match::new(found.to_vec()))
+    });
+    match result {
+        Ok((entry_count, errors)) => {
+            for error in errors {
+                println!("Ignoring entry, because of conversion error: {}", error);
+            }
+            println!("Streamed {} entries.", entry_count);
+        }
+        Err(e) => println!("Streaming failed: {}", e),
+    }
+}
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(|mode| mode.as_str()) == Some("stream") {
+        print_streamed_sums(&args[1..]);
+        return;
+    }
+
     let input_string = read_to_string("input.txt").unwrap();
 
-    let mut expense_report = Vec::new();
-    for entry in input_string.split('\n') {
-        match entry.parse::<i32>() {
-            Ok(entry_value) => expense_report.push(entry_value),
-            Err(e) => println!("Ignoring entry, because of conversion error: {}", e),
-        };
+    let (expense_report, errors) = ExpenseReport::parse_lenient(&input_string);
+    for error in errors {
+        println!("Ignoring entry, because of conversion error: {}", error);
     }
 
     println!(
         "Got {} entries. All entries:\n{:?}",
         expense_report.len(),
-        expense_report
+        expense_report.entries()
     );
 
-    for (entry_outer_id, entry_outer) in expense_report.iter().enumerate() {
-        for (entry_middle_id, entry_middle) in expense_report[entry_outer_id..].iter().enumerate() {
-            for entry_inner in &expense_report[entry_middle_id..] {
-                if entry_inner + entry_middle + entry_outer == 2020 {
-                    println!(
-                        "Found valid entry triplet 2020 = {} + {} + {}",
-                        entry_inner, entry_middle, entry_outer
-                    );
-                    println!(
-                        "{} * {} * {} = {}",
-                        entry_inner,
-                        entry_middle,
-                        entry_outer,
-                        entry_outer * entry_middle * entry_inner
-                    );
-                }
-            }
-        }
+    match args.first().map(|mode| mode.as_str()) {
+        Some("ksum") => print_best_k_sum(&expense_report, &args[1..]),
+        Some("subset") => print_subset(&expense_report, &args[1..]),
+        _ => print_k_sums(&expense_report),
     }
 }
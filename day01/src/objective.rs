@@ -0,0 +1,169 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operation {
+    Product,
+    SumOfSquares,
+}
+
+/// The result of an operation on the entries doesn't fit into an `i128`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArithmeticOverflow {
+    pub operation: Operation,
+    pub entries: Vec<i32>,
+}
+
+impl fmt::Display for ArithmeticOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let operation = match self.operation {
+            Operation::Product => "product",
+            Operation::SumOfSquares => "sum of squares",
+        };
+        write!(f, "{} of {:?} overflows i128", operation, self.entries)
+    }
+}
+
+impl std::error::Error for ArithmeticOverflow {}
+
+/// What to optimize when selecting one of several matches.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Objective {
+    MaxProduct,
+    MinProduct,
+    MaxSumOfSquares,
+    MinSumOfSquares,
+}
+
+/// A combination of entries found by a search, with its widened sum and checked product.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match {
+    pub entries: Vec<i32>,
+    pub sum: i64,
+    pub product: Result<i128, ArithmeticOverflow>,
+}
+
+impl Match {
+    pub fn new(entries: Vec<i32>) -> Match {
+        Match {
+            sum: entries.iter().map(|&entry| entry as i64).sum(),
+            product: checked_product(&entries),
+            entries,
+        }
+    }
+
+    pub fn sum_of_squares(&self) -> Result<i128, ArithmeticOverflow> {
+        self.entries
+            .iter()
+            .try_fold(0i128, |sum, &entry| {
+                sum.checked_add(entry as i128 * entry as i128)
+            })
+            .ok_or_else(|| ArithmeticOverflow {
+                operation: Operation::SumOfSquares,
+                entries: self.entries.clone(),
+            })
+    }
+
+    /// The value of the objective for this match. Larger is better.
+    fn score(&self, objective: Objective) -> Result<i128, ArithmeticOverflow> {
+        let negated = |value: i128, operation| {
+            value.checked_neg().ok_or_else(|| ArithmeticOverflow {
+                operation,
+                entries: self.entries.clone(),
+            })
+        };
+        match objective {
+            Objective::MaxProduct => self.product.clone(),
+            Objective::MinProduct => negated(self.product.clone()?, Operation::Product),
+            Objective::MaxSumOfSquares => self.sum_of_squares(),
+            Objective::MinSumOfSquares => negated(self.sum_of_squares()?, Operation::SumOfSquares),
+        }
+    }
+}
+
+pub fn checked_product(entries: &[i32]) -> Result<i128, ArithmeticOverflow> {
+    entries
+        .iter()
+        .try_fold(1i128, |product, &entry| product.checked_mul(entry as i128))
+        .ok_or_else(|| ArithmeticOverflow {
+            operation: Operation::Product,
+            entries: entries.to_vec(),
+        })
+}
+
+/// Selects the match that optimizes the objective. On ties, the first of them is selected.
+///
+/// Fails if the objective can't be computed for any of the matches, instead of comparing wrapped values.
+pub fn select_best(
+    matches: &[Match],
+    objective: Objective,
+) -> Result<Option<&Match>, ArithmeticOverflow> {
+    let mut best: Option<(i128, &Match)> = None;
+    for candidate in matches {
+        let score = candidate.score(objective)?;
+        if best.is_none_or(|(best_score, _)| score > best_score) {
+            best = Some((score, candidate));
+        }
+    }
+    Ok(best.map(|(_, best)| best))
+}
+
+#[test]
+fn test_match_product_and_sum() {
+    let found = Match::new(vec![366, 675, 979]);
+    assert_eq!(found.sum, 2020);
+    assert_eq!(found.product, Ok(241861950));
+
+    // overflows i32, but not i128
+    let found = Match::new(vec![i32::MAX, i32::MAX, -2]);
+    assert_eq!(found.sum, i32::MAX as i64 * 2 - 2);
+    assert_eq!(found.product, Ok(-2 * i32::MAX as i128 * i32::MAX as i128));
+    assert_eq!(
+        found.sum_of_squares(),
+        Ok(2 * i32::MAX as i128 * i32::MAX as i128 + 4)
+    );
+
+    let found = Match::new(vec![i32::MIN; 5]);
+    assert_eq!(
+        found.product,
+        Err(ArithmeticOverflow {
+            operation: Operation::Product,
+            entries: vec![i32::MIN; 5]
+        })
+    );
+    assert_eq!(
+        found.product.unwrap_err().to_string(),
+        "product of [-2147483648, -2147483648, -2147483648, -2147483648, -2147483648] overflows i128"
+    );
+}
+
+#[test]
+fn test_select_best() {
+    let matches = vec![
+        Match::new(vec![-10, 30]),
+        Match::new(vec![5, 15]),
+        Match::new(vec![10, 10]),
+    ];
+    let select = |objective| select_best(&matches, objective).unwrap().unwrap();
+    assert_eq!(select(Objective::MaxProduct).entries, [10, 10]);
+    assert_eq!(select(Objective::MinProduct).entries, [-10, 30]);
+    assert_eq!(select(Objective::MaxSumOfSquares).entries, [-10, 30]);
+    assert_eq!(select(Objective::MinSumOfSquares).entries, [10, 10]);
+    assert_eq!(select_best(&[], Objective::MaxProduct), Ok(None));
+
+    let matches = vec![Match::new(vec![1]), Match::new(vec![i32::MAX; 5])];
+    assert!(select_best(&matches, Objective::MaxProduct).is_err());
+    assert!(select_best(&matches, Objective::MaxSumOfSquares).is_ok());
+
+    // the product is exactly i128::MIN, which can't be negated
+    let entries = vec![i32::MIN, i32::MIN, i32::MIN, 1 << 30, 16];
+    let matches = vec![Match::new(vec![1]), Match::new(entries.clone())];
+    assert_eq!(matches[1].product, Ok(i128::MIN));
+    assert_eq!(
+        select_best(&matches, Objective::MinProduct),
+        Err(ArithmeticOverflow {
+            operation: Operation::Product,
+            entries
+        })
+    );
+    assert!(select_best(&matches, Objective::MaxProduct).is_ok());
+}
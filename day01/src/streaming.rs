@@ -0,0 +1,697 @@
+use crate::expense_report::{EntryParseError, ParseMode};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, Read, Seek, SeekFrom, Write};
+use std::ops::Bound;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Size of one spilled record: the value as i32 and its count as u8.
+const RECORD_SIZE: u64 = 5;
+
+static NEXT_SPILL_ID: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone)]
+pub struct StreamingConfig {
+    /// Maximum number of distinct values kept in memory before they are spilled to disk.
+    pub memory_budget: usize,
+    /// Directory for the spill files. They are removed again when the finder is dropped.
+    pub spill_dir: PathBuf,
+}
+
+impl Default for StreamingConfig {
+    fn default() -> Self {
+        StreamingConfig {
+            memory_budget: 1 << 20,
+            spill_dir: std::env::temp_dir(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum StreamError {
+    Io(io::Error),
+    Parse(EntryParseError),
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StreamError::Io(e) => write!(f, "io error: {}", e),
+            StreamError::Parse(e) => write!(f, "parse error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {}
+
+impl From<io::Error> for StreamError {
+    fn from(e: io::Error) -> Self {
+        StreamError::Io(e)
+    }
+}
+
+/// Number of records a `RunCursor` reads at once.
+const CURSOR_CHUNK: u64 = 4096;
+
+fn decode_record(record: &[u8]) -> (i32, u8) {
+    (
+        i32::from_le_bytes([record[0], record[1], record[2], record[3]]),
+        record[4],
+    )
+}
+
+fn read_record(file: &File, id: u64) -> io::Result<(i32, u8)> {
+    let mut record = [0; RECORD_SIZE as usize];
+    let mut file = file;
+    file.seek(SeekFrom::Start(id * RECORD_SIZE))?;
+    file.read_exact(&mut record)?;
+    Ok(decode_record(&record))
+}
+
+/// First record in `low..high` whose value isn't below `bound`, by binary search.
+fn lower_bound(file: &File, mut low: u64, mut high: u64, bound: i32) -> io::Result<u64> {
+    while low < high {
+        let middle = (low + high) / 2;
+        if read_record(file, middle)?.0 < bound {
+            low = middle + 1;
+        } else {
+            high = middle;
+        }
+    }
+    Ok(low)
+}
+
+/// A sorted run of `(value, count)` records on disk, each value at most once.
+struct SpillRun {
+    path: PathBuf,
+    file: File,
+    len: u64,
+}
+
+impl SpillRun {
+    /// Writes the values in ascending order, so that they are a new sorted run.
+    fn write(path: PathBuf, mut values: SortedValues) -> io::Result<SpillRun> {
+        let mut writer = io::BufWriter::new(File::create(&path)?);
+        let mut len = 0;
+        while let Some((value, count)) = values.next()? {
+            writer.write_all(&value.to_le_bytes())?;
+            writer.write_all(&[count])?;
+            len += 1;
+        }
+        writer.flush()?;
+        Ok(SpillRun {
+            file: File::open(&path)?,
+            path,
+            len,
+        })
+    }
+
+    /// Binary search for the value's count.
+    fn count(&self, value: i32) -> io::Result<u8> {
+        let id = lower_bound(&self.file, 0, self.len, value)?;
+        if id == self.len {
+            return Ok(0);
+        }
+        match read_record(&self.file, id)? {
+            (found, count) if found == value => Ok(count),
+            _ => Ok(0),
+        }
+    }
+
+    fn cursor(&self, ascending: bool) -> RunCursor<'_> {
+        RunCursor {
+            file: &self.file,
+            start: 0,
+            end: self.len,
+            ascending,
+            buffer: Vec::new(),
+        }
+    }
+}
+
+impl Drop for SpillRun {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Reads the records of a run in chunks, from either end.
+struct RunCursor<'a> {
+    file: &'a File,
+    /// The records `start..end` haven't been read yet.
+    start: u64,
+    end: u64,
+    ascending: bool,
+    /// Records read but not returned yet, the next one last.
+    buffer: Vec<(i32, u8)>,
+}
+
+impl RunCursor<'_> {
+    fn next(&mut self) -> io::Result<Option<(i32, u8)>> {
+        if self.buffer.is_empty() && self.start < self.end {
+            let chunk = CURSOR_CHUNK.min(self.end - self.start);
+            let first = match self.ascending {
+                true => self.start,
+                false => self.end - chunk,
+            };
+            let mut bytes = vec![0; (chunk * RECORD_SIZE) as usize];
+            let mut file = self.file;
+            file.seek(SeekFrom::Start(first * RECORD_SIZE))?;
+            file.read_exact(&mut bytes)?;
+            self.buffer = bytes
+                .chunks(RECORD_SIZE as usize)
+                .map(decode_record)
+                .collect();
+            if self.ascending {
+                self.buffer.reverse();
+                self.start += chunk;
+            } else {
+                self.end -= chunk;
+            }
+        }
+        Ok(self.buffer.pop())
+    }
+
+    /// Skips the records before `bound` in the cursor's order.
+    /// Beyond the buffered chunk, this is a binary search, so skipped chunks are never read.
+    fn skip_to(&mut self, bound: i32) -> io::Result<()> {
+        let ascending = self.ascending;
+        while let Some(&(value, _)) = self.buffer.last() {
+            if (value < bound) != ascending || value == bound {
+                return Ok(());
+            }
+            self.buffer.pop();
+        }
+        if ascending {
+            self.start = lower_bound(self.file, self.start, self.end, bound)?;
+        } else {
+            self.end = match bound {
+                i32::MAX => self.end,
+                _ => lower_bound(self.file, self.start, self.end, bound + 1)?,
+            };
+        }
+        Ok(())
+    }
+}
+
+/// Where sorted values come from, see `SortedValues`.
+enum Source<'a> {
+    /// The values after `bound` in the iteration order.
+    Memory {
+        values: &'a BTreeMap<i32, u8>,
+        bound: Bound<i32>,
+    },
+    Run(RunCursor<'a>),
+}
+
+impl Source<'_> {
+    fn next(&mut self, ascending: bool) -> io::Result<Option<(i32, u8)>> {
+        match self {
+            Source::Memory { values, bound } => {
+                let next = match ascending {
+                    true => values.range((*bound, Bound::Unbounded)).next(),
+                    false => values.range((Bound::Unbounded, *bound)).next_back(),
+                };
+                let next = next.map(|(&value, &count)| (value, count));
+                if let Some((value, _)) = next {
+                    *bound = Bound::Excluded(value);
+                }
+                Ok(next)
+            }
+            Source::Run(cursor) => cursor.next(),
+        }
+    }
+
+    fn skip_to(&mut self, bound: i32) -> io::Result<()> {
+        match self {
+            Source::Memory { bound: current, .. } => *current = Bound::Included(bound),
+            Source::Run(cursor) => cursor.skip_to(bound)?,
+        }
+        Ok(())
+    }
+}
+
+/// The distinct seen values with their counts, in ascending or descending order,
+/// merged from any number of sources.
+struct SortedValues<'a> {
+    sources: Vec<Source<'a>>,
+    /// The next value of each source.
+    heads: Vec<Option<(i32, u8)>>,
+    ascending: bool,
+    max_count: u8,
+}
+
+impl<'a> SortedValues<'a> {
+    fn new(
+        mut sources: Vec<Source<'a>>,
+        ascending: bool,
+        max_count: u8,
+    ) -> io::Result<SortedValues<'a>> {
+        let heads = sources
+            .iter_mut()
+            .map(|source| source.next(ascending))
+            .collect::<io::Result<_>>()?;
+        Ok(SortedValues {
+            sources,
+            heads,
+            ascending,
+            max_count,
+        })
+    }
+
+    fn is_before(&self, value: i32, other: i32) -> bool {
+        (value < other) == self.ascending && value != other
+    }
+
+    fn next(&mut self) -> io::Result<Option<(i32, u8)>> {
+        let mut next: Option<i32> = None;
+        for &(value, _) in self.heads.iter().flatten() {
+            if next.is_none_or(|next| self.is_before(value, next)) {
+                next = Some(value);
+            }
+        }
+        let next = match next {
+            Some(next) => next,
+            None => return Ok(None),
+        };
+        let mut count = 0u8;
+        for (source, head) in self.sources.iter_mut().zip(&mut self.heads) {
+            if let Some((value, head_count)) = *head {
+                if value == next {
+                    count = count.saturating_add(head_count);
+                    *head = source.next(self.ascending)?;
+                }
+            }
+        }
+        Ok(Some((next, count.min(self.max_count))))
+    }
+
+    /// Skips the values before `bound` in the iteration order.
+    fn skip_to(&mut self, bound: i32) -> io::Result<()> {
+        for id in 0..self.sources.len() {
+            if let Some((value, _)) = self.heads[id] {
+                if self.is_before(value, bound) {
+                    self.sources[id].skip_to(bound)?;
+                    self.heads[id] = self.sources[id].next(self.ascending)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Multiset of the values seen so far, with bounded memory.
+///
+/// Values beyond the memory budget are spilled into sorted runs on disk. A new run is merged with
+/// the previous one while that one isn't more than twice as large, so there are only logarithmically
+/// many runs and each value is rewritten only a logarithmic number of times.
+/// Counts are a binary search per run and sorted iteration merges the runs sequentially.
+/// Counts are capped, because a combination can't use more copies of a value than it has entries.
+struct SeenValues {
+    in_memory: BTreeMap<i32, u8>,
+    /// Oldest and largest first.
+    runs: Vec<SpillRun>,
+    spill_count: usize,
+    runs_written: usize,
+    max_count: u8,
+    config: StreamingConfig,
+    spill_id: usize,
+}
+
+impl SeenValues {
+    fn count(&self, value: i32) -> io::Result<u8> {
+        let mut count = self.in_memory.get(&value).cloned().unwrap_or(0);
+        for run in &self.runs {
+            if count >= self.max_count {
+                break;
+            }
+            count = count.saturating_add(run.count(value)?);
+        }
+        Ok(count.min(self.max_count))
+    }
+
+    fn insert(&mut self, value: i32) -> io::Result<()> {
+        if !self.in_memory.contains_key(&value) && self.in_memory.len() >= self.config.memory_budget
+        {
+            self.spill()?;
+        }
+        let max_count = self.max_count;
+        let count = self.in_memory.entry(value).or_insert(0);
+        *count = (*count + 1).min(max_count);
+        Ok(())
+    }
+
+    fn write_run(&mut self, sources: Vec<Source>) -> io::Result<SpillRun> {
+        let path = self.config.spill_dir.join(format!(
+            "day01-spill-{}-{}-{}.bin",
+            std::process::id(),
+            self.spill_id,
+            self.runs_written
+        ));
+        self.runs_written += 1;
+        SpillRun::write(path, SortedValues::new(sources, true, self.max_count)?)
+    }
+
+    /// Writes the values in memory into a new run and merges runs of similar size.
+    fn spill(&mut self) -> io::Result<()> {
+        let in_memory = std::mem::take(&mut self.in_memory);
+        let run = self.write_run(vec![Source::Memory {
+            values: &in_memory,
+            bound: Bound::Unbounded,
+        }])?;
+        self.runs.push(run);
+        while let [.., older, newer] = self.runs.as_slice() {
+            if older.len > 2 * newer.len {
+                break;
+            }
+            let newer = self.runs.pop().unwrap();
+            let older = self.runs.pop().unwrap();
+            let merged = self.write_run(vec![
+                Source::Run(older.cursor(true)),
+                Source::Run(newer.cursor(true)),
+            ])?;
+            self.runs.push(merged);
+        }
+        self.spill_count += 1;
+        Ok(())
+    }
+
+    fn sorted(&self, ascending: bool) -> io::Result<SortedValues<'_>> {
+        let mut sources = vec![Source::Memory {
+            values: &self.in_memory,
+            bound: Bound::Unbounded,
+        }];
+        sources.extend(
+            self.runs
+                .iter()
+                .map(|run| Source::Run(run.cursor(ascending))),
+        );
+        SortedValues::new(sources, ascending, self.max_count)
+    }
+}
+
+/// Finds pairs or triplets summing up to a target in a stream of entries.
+///
+/// Matches are reported as soon as their last entry has been pushed.
+/// Each distinct combination is reported once, with its values sorted ascending:
+/// a combination is new exactly when the pushed entry is its last missing copy of that value.
+pub struct StreamingSumFinder {
+    k: usize,
+    target: i64,
+    seen: SeenValues,
+}
+
+impl StreamingSumFinder {
+    /// Only pairs (`k == 2`) and triplets (`k == 3`) are supported.
+    pub fn new(k: usize, target: i32, config: StreamingConfig) -> StreamingSumFinder {
+        assert!(k == 2 || k == 3, "only pairs and triplets can be streamed");
+        StreamingSumFinder {
+            k,
+            target: target as i64,
+            seen: SeenValues {
+                in_memory: BTreeMap::new(),
+                runs: Vec::new(),
+                spill_count: 0,
+                runs_written: 0,
+                max_count: k as u8,
+                config,
+                spill_id: NEXT_SPILL_ID.fetch_add(1, Ordering::Relaxed),
+            },
+        }
+    }
+
+    /// Number of times the values in memory were spilled to disk so far.
+    pub fn spill_count(&self) -> usize {
+        self.seen.spill_count
+    }
+
+    /// Adds an entry and returns the new matches it completes.
+    ///
+    /// Pairs need two lookups. Triplets need one pass over the distinct values seen so far from both ends,
+    /// which jumps over values without a partner, so that only the chunks of the runs that can be part
+    /// of a match are read.
+    pub fn push(&mut self, entry: i32) -> io::Result<Vec<Vec<i32>>> {
+        let rest = self.target - entry as i64;
+        let entry_count = self.seen.count(entry)?;
+        let mut matches = Vec::new();
+
+        if self.k == 2 {
+            if let Some(other) = as_i32(rest) {
+                let is_new = match other == entry {
+                    true => entry_count == 1,
+                    false => entry_count == 0 && self.seen.count(other)? >= 1,
+                };
+                if is_new {
+                    matches.push(vec![entry.min(other), entry.max(other)]);
+                }
+            }
+        } else {
+            let mut low_values = self.seen.sorted(true)?;
+            let mut high_values = self.seen.sorted(false)?;
+            let (mut low, mut high) = (low_values.next()?, high_values.next()?);
+            while let (Some((low_value, low_count)), Some((high_value, _))) = (low, high) {
+                if low_value > high_value {
+                    break;
+                }
+                let sum = low_value as i64 + high_value as i64;
+                if sum < rest {
+                    low_values.skip_to(clamp_to_i32(rest - high_value as i64))?;
+                    low = low_values.next()?;
+                } else if sum > rest {
+                    high_values.skip_to(clamp_to_i32(rest - low_value as i64))?;
+                    high = high_values.next()?;
+                } else {
+                    let copies_of_entry =
+                        1 + (low_value == entry) as u8 + (high_value == entry) as u8;
+                    if (low_value != high_value || low_count >= 2)
+                        && entry_count == copies_of_entry - 1
+                    {
+                        let mut found = vec![entry, low_value, high_value];
+                        found.sort_unstable();
+                        matches.push(found);
+                    }
+                    low = low_values.next()?;
+                    high = high_values.next()?;
+                }
+            }
+        }
+        self.seen.insert(entry)?;
+        Ok(matches)
+    }
+}
+
+fn clamp_to_i32(value: i64) -> i32 {
+    value.max(i32::MIN as i64).min(i32::MAX as i64) as i32
+}
+
+fn as_i32(value: i64) -> Option<i32> {
+    if value < i32::MIN as i64 || value > i32::MAX as i64 {
+        None
+    } else {
+        Some(value as i32)
+    }
+}
+
+/// Reads one entry per line and calls `on_match` for every match as soon as it is found.
+///
+/// Returns the number of entries read and, in lenient mode, the lines that were skipped.
+pub fn find_sums_in_reader<R: BufRead>(
+    reader: R,
+    k: usize,
+    target: i32,
+    mode: ParseMode,
+    config: StreamingConfig,
+    mut on_match: impl FnMut(&[i32]),
+) -> Result<(usize, Vec<EntryParseError>), StreamError> {
+    let mut finder = StreamingSumFinder::new(k, target, config);
+    let mut entry_count = 0;
+    let mut errors = Vec::new();
+
+    for (line_id, line) in reader.lines().enumerate() {
+        let line = line?;
+        let raw = line.trim();
+        if raw.is_empty() {
+            continue;
+        }
+        match raw.parse::<i32>() {
+            Ok(entry) => {
+                entry_count += 1;
+                for found in finder.push(entry)? {
+                    on_match(&found);
+                }
+            }
+            Err(cause) => {
+                let error = EntryParseError {
+                    line: line_id + 1,
+                    raw: raw.to_string(),
+                    cause,
+                };
+                match mode {
+                    ParseMode::Strict => return Err(StreamError::Parse(error)),
+                    ParseMode::Lenient => errors.push(error),
+                }
+            }
+        }
+    }
+    Ok((entry_count, errors))
+}
+
+#[cfg(test)]
+fn stream_all(entries: &[i32], k: usize, target: i32, memory_budget: usize) -> Vec<Vec<i32>> {
+    let config = StreamingConfig {
+        memory_budget,
+        ..StreamingConfig::default()
+    };
+    let mut finder = StreamingSumFinder::new(k, target, config);
+    let mut matches = Vec::new();
+    for &entry in entries {
+        matches.extend(finder.push(entry).unwrap());
+    }
+    matches
+}
+
+#[test]
+fn test_streaming_example() {
+    let entries = [1721, 979, 366, 299, 675, 1456];
+    assert_eq!(stream_all(&entries, 2, 2020, 100), [[299, 1721]]);
+    assert_eq!(stream_all(&entries, 3, 2020, 100), [[366, 675, 979]]);
+}
+
+#[test]
+fn test_streaming_emits_matches_early() {
+    let config = StreamingConfig::default();
+    let mut finder = StreamingSumFinder::new(2, 10, config);
+    assert!(finder.push(3).unwrap().is_empty());
+    assert!(finder.push(5).unwrap().is_empty());
+    assert_eq!(finder.push(7).unwrap(), [[3, 7]]);
+    assert_eq!(finder.push(5).unwrap(), [[5, 5]]);
+    assert!(finder.push(5).unwrap().is_empty()); // already reported
+    assert!(finder.push(3).unwrap().is_empty());
+}
+
+#[test]
+fn test_streaming_with_spilling_matches_in_memory_search() {
+    let entries: Vec<i32> = (0..150).map(|i| (i * 7919 % 211) - 50).collect();
+    for &k in &[2, 3] {
+        for &target in &[-20, 0, 77, 150] {
+            let mut expected = crate::k_sum::find_k_sum(&entries, k, target);
+            expected.sort();
+
+            let mut in_memory = stream_all(&entries, k, target, usize::MAX);
+            in_memory.sort();
+            assert_eq!(in_memory, expected);
+
+            let config = StreamingConfig {
+                memory_budget: 16,
+                ..StreamingConfig::default()
+            };
+            let mut finder = StreamingSumFinder::new(k, target, config);
+            let mut spilled = Vec::new();
+            for &entry in &entries {
+                spilled.extend(finder.push(entry).unwrap());
+            }
+            assert!(finder.spill_count() > 0);
+            spilled.sort();
+            assert_eq!(spilled, expected);
+        }
+    }
+}
+
+#[test]
+fn test_find_sums_in_reader() {
+    let input = "1721\n979\nabc\n366\n299\n\n675\n1456\n";
+    let mut matches = Vec::new();
+    let (entry_count, errors) = find_sums_in_reader(
+        input.as_bytes(),
+        3,
+        2020,
+        ParseMode::Lenient,
+        StreamingConfig::default(),
+        |found| matches.push(found.to_vec()),
+    )
+    .unwrap();
+    assert_eq!(entry_count, 6);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].line, 3);
+    assert_eq!(matches, [[366, 675, 979]]);
+
+    let result = find_sums_in_reader(
+        input.as_bytes(),
+        3,
+        2020,
+        ParseMode::Strict,
+        StreamingConfig::default(),
+        |_| {},
+    );
+    assert!(matches!(result, Err(StreamError::Parse(e)) if e.line == 3));
+}
+
+#[test]
+fn test_streaming_duplicates_are_reported_once() {
+    let entries: Vec<i32> = (0..300).map(|i| (i * 37 % 23) - 5).collect();
+    for &k in &[2, 3] {
+        for &target in &[-15, 0, 6, 30] {
+            let mut expected = crate::k_sum::find_k_sum(&entries, k, target);
+            expected.sort();
+            for &memory_budget in &[1, 4, usize::MAX] {
+                let mut found = stream_all(&entries, k, target, memory_budget);
+                found.sort();
+                assert_eq!(found, expected, "k {} target {}", k, target);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_streaming_merges_spilled_runs() {
+    let spill_dir = std::env::temp_dir().join(format!("day01-merge-test-{}", std::process::id()));
+    fs::create_dir_all(&spill_dir).unwrap();
+    let config = StreamingConfig {
+        memory_budget: 8,
+        spill_dir: spill_dir.clone(),
+    };
+    let mut finder = StreamingSumFinder::new(3, 2020, config);
+    for entry in 0..100 {
+        finder.push(entry).unwrap();
+    }
+    assert!(finder.spill_count() > 10);
+    let run_lens: Vec<u64> = finder.seen.runs.iter().map(|run| run.len).collect();
+    assert!(run_lens.windows(2).all(|lens| lens[0] > 2 * lens[1]));
+    assert_eq!(
+        run_lens.iter().sum::<u64>(),
+        100 - finder.seen.in_memory.len() as u64
+    );
+    assert_eq!(fs::read_dir(&spill_dir).unwrap().count(), run_lens.len());
+    drop(finder);
+    assert_eq!(fs::read_dir(&spill_dir).unwrap().count(), 0);
+    fs::remove_dir(&spill_dir).unwrap();
+}
+
+#[test]
+fn test_run_cursor_skips_chunks() {
+    let values: BTreeMap<i32, u8> = (0..10_000).map(|value| (value * 2, 1)).collect();
+    let path = std::env::temp_dir().join(format!("day01-cursor-test-{}.bin", std::process::id()));
+    let sources = vec![Source::Memory {
+        values: &values,
+        bound: Bound::Unbounded,
+    }];
+    let run = SpillRun::write(path, SortedValues::new(sources, true, 3).unwrap()).unwrap();
+
+    let mut ascending = run.cursor(true);
+    assert_eq!(ascending.next().unwrap(), Some((0, 1)));
+    ascending.skip_to(17_001).unwrap();
+    assert_eq!(ascending.next().unwrap(), Some((17_002, 1)));
+    ascending.skip_to(i32::MAX).unwrap();
+    assert_eq!(ascending.next().unwrap(), None);
+
+    let mut descending = run.cursor(false);
+    descending.skip_to(1_001).unwrap();
+    assert_eq!(descending.next().unwrap(), Some((1_000, 1)));
+    descending.skip_to(4).unwrap();
+    assert_eq!(descending.next().unwrap(), Some((4, 1)));
+    assert_eq!(run.count(4).unwrap(), 1);
+    assert_eq!(run.count(5).unwrap(), 0);
+    assert_eq!(run.count(i32::MAX).unwrap(), 0);
+}
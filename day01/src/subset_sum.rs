@@ -0,0 +1,321 @@
+use std::fmt;
+
+/// Upper bound for the bytes allocated by the DP tables, to avoid allocating absurd amounts of memory.
+///
+/// Each sum of a table takes a bit for whether it's reachable and a `u32` for the entry that reached it.
+pub const MAX_TABLE_BYTES: u64 = 1 << 28;
+
+const NO_PARENT: u32 = u32::MAX;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SubsetSumError {
+    /// The sums reachable by the entries span too many values for the DP table.
+    TableTooLarge { bytes: u64, limit: u64 },
+    /// More entries than the DP table can reference.
+    TooManyEntries(usize),
+}
+
+impl fmt::Display for SubsetSumError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SubsetSumError::TableTooLarge { bytes, limit } => write!(
+                f,
+                "subset sum tables would need {} bytes, but only {} are allowed",
+                bytes, limit
+            ),
+            SubsetSumError::TooManyEntries(count) => {
+                write!(f, "too many entries for the subset sum solver: {}", count)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SubsetSumError {}
+
+/// Bitset over all sums in `min_sum..=max_sum`, where bit `i` stands for the sum `min_sum + i`.
+#[derive(Debug, Clone)]
+struct SumSet {
+    words: Vec<u64>,
+}
+
+impl SumSet {
+    fn new(size: usize) -> SumSet {
+        SumSet {
+            words: vec![0; size.div_ceil(64)],
+        }
+    }
+
+    fn contains(&self, bit: usize) -> bool {
+        self.words[bit / 64] & (1 << (bit % 64)) != 0
+    }
+
+    fn insert(&mut self, bit: usize) {
+        self.words[bit / 64] |= 1 << (bit % 64);
+    }
+
+    /// Returns the sums that become reachable by adding `shift` to any sum in this set,
+    /// and that aren't contained in `known` yet.
+    fn shifted_new(&self, shift: i64, known: &SumSet) -> Vec<(usize, u64)> {
+        let word_shift = (shift.unsigned_abs() / 64) as usize;
+        let bit_shift = (shift.unsigned_abs() % 64) as u32;
+        let word_count = self.words.len();
+        let word = |id: isize| {
+            if id < 0 || id as usize >= word_count {
+                0
+            } else {
+                self.words[id as usize]
+            }
+        };
+
+        let mut new_words = Vec::new();
+        for id in 0..word_count {
+            let shifted = if shift >= 0 {
+                let source = id as isize - word_shift as isize;
+                match bit_shift {
+                    0 => word(source),
+                    _ => (word(source) << bit_shift) | (word(source - 1) >> (64 - bit_shift)),
+                }
+            } else {
+                let source = id as isize + word_shift as isize;
+                match bit_shift {
+                    0 => word(source),
+                    _ => (word(source) >> bit_shift) | (word(source + 1) << (64 - bit_shift)),
+                }
+            };
+            let new = shifted & !known.words[id];
+            if new != 0 {
+                new_words.push((id, new));
+            }
+        }
+        new_words
+    }
+}
+
+/// Calls `f` with the index of every set bit in the `(word index, word)` pairs.
+fn for_each_bit(words: &[(usize, u64)], mut f: impl FnMut(usize)) {
+    for &(id, mut word) in words {
+        while word != 0 {
+            f(id * 64 + word.trailing_zeros() as usize);
+            word &= word - 1;
+        }
+    }
+}
+
+/// The range of all sums the entries can reach. Negative entries lower the minimum.
+fn sum_range(entries: &[i32]) -> (i64, i64) {
+    let min_sum = entries.iter().filter(|&&e| e < 0).map(|&e| e as i64).sum();
+    let max_sum = entries.iter().filter(|&&e| e > 0).map(|&e| e as i64).sum();
+    (min_sum, max_sum)
+}
+
+/// Bytes of a `SumSet` and a parent table with `width` sums each, times `rows`.
+fn table_bytes(width: u64, rows: u64) -> u64 {
+    let row_bytes = width
+        .saturating_mul(std::mem::size_of::<u32>() as u64)
+        .saturating_add(width.div_ceil(64) * 8);
+    row_bytes.saturating_mul(rows)
+}
+
+fn check_table_size(entries: &[i32], rows: u64) -> Result<(i64, usize), SubsetSumError> {
+    if entries.len() >= NO_PARENT as usize {
+        return Err(SubsetSumError::TooManyEntries(entries.len()));
+    }
+    let (min_sum, max_sum) = sum_range(entries);
+    let width = (max_sum - min_sum) as u64 + 1;
+    let bytes = table_bytes(width, rows);
+    if bytes > MAX_TABLE_BYTES {
+        return Err(SubsetSumError::TableTooLarge {
+            bytes,
+            limit: MAX_TABLE_BYTES,
+        });
+    }
+    Ok((min_sum, width as usize))
+}
+
+/// Finds a subset of the entries, of any size, that sums up to `target`.
+///
+/// Returns the indices of the chosen entries in ascending order, or `None` if no subset exists.
+/// A `target` of zero is always reached by the empty subset.
+pub fn find_subset_sum(entries: &[i32], target: i32) -> Result<Option<Vec<usize>>, SubsetSumError> {
+    let (min_sum, width) = check_table_size(entries, 1)?;
+    let target = target as i64;
+    if target < min_sum || target >= min_sum + width as i64 {
+        return Ok(None);
+    }
+
+    // parents[sum] is the first entry whose addition made the sum reachable
+    let mut reachable = SumSet::new(width);
+    let mut parents = vec![NO_PARENT; width];
+    reachable.insert((-min_sum) as usize);
+
+    for (id, &entry) in entries.iter().enumerate() {
+        let new_sums = reachable.shifted_new(entry as i64, &reachable);
+        for_each_bit(&new_sums, |bit| {
+            reachable.insert(bit);
+            parents[bit] = id as u32;
+        });
+    }
+
+    let target_bit = (target - min_sum) as usize;
+    if !reachable.contains(target_bit) {
+        return Ok(None);
+    }
+    Ok(Some(reconstruct(entries, min_sum, target_bit, |bit| {
+        parents[bit]
+    })))
+}
+
+/// Finds the subset of at most `max_entries` entries whose sum is closest to `target`.
+///
+/// Ties are resolved in favour of fewer entries, then of the smaller sum.
+/// Returns the indices of the chosen entries in ascending order.
+pub fn find_closest_subset(
+    entries: &[i32],
+    target: i32,
+    max_entries: usize,
+) -> Result<Vec<usize>, SubsetSumError> {
+    let max_entries = max_entries.min(entries.len());
+    let (min_sum, width) = check_table_size(entries, max_entries as u64 + 1)?;
+
+    // one bitset and parent table per number of chosen entries
+    let mut reachable = vec![SumSet::new(width); max_entries + 1];
+    let mut parents = vec![vec![NO_PARENT; width]; max_entries + 1];
+    reachable[0].insert((-min_sum) as usize);
+
+    for (id, &entry) in entries.iter().enumerate() {
+        // go from many to few entries, so that each entry is used at most once
+        for count in (0..max_entries).rev() {
+            let new_sums = reachable[count].shifted_new(entry as i64, &reachable[count + 1]);
+            for_each_bit(&new_sums, |bit| {
+                reachable[count + 1].insert(bit);
+                parents[count + 1][bit] = id as u32;
+            });
+        }
+    }
+
+    let target = target as i64;
+    let mut best: Option<(u64, usize, usize)> = None; // (distance, count, bit)
+    for (count, sums) in reachable.iter().enumerate() {
+        for (word_id, &word) in sums.words.iter().enumerate() {
+            for_each_bit(&[(word_id, word)], |bit| {
+                let distance = (min_sum + bit as i64 - target).unsigned_abs();
+                if best.is_none_or(|best| (distance, count, bit) < best) {
+                    best = Some((distance, count, bit));
+                }
+            });
+        }
+    }
+    let (_, count, bit) = best.expect("the empty subset is always reachable");
+
+    let mut remaining = count;
+    Ok(reconstruct(entries, min_sum, bit, |bit| {
+        if remaining == 0 {
+            return NO_PARENT;
+        }
+        let parent = parents[remaining][bit];
+        remaining -= 1;
+        parent
+    }))
+}
+
+/// Walks back from `bit` along the parents until the empty sum is reached.
+fn reconstruct(
+    entries: &[i32],
+    min_sum: i64,
+    mut bit: usize,
+    mut parent_of: impl FnMut(usize) -> u32,
+) -> Vec<usize> {
+    let mut chosen = Vec::new();
+    loop {
+        let parent = parent_of(bit);
+        if parent == NO_PARENT {
+            break;
+        }
+        chosen.push(parent as usize);
+        bit = (bit as i64 - entries[parent as usize] as i64) as usize;
+    }
+    debug_assert_eq!(bit as i64 + min_sum, 0);
+    chosen.reverse();
+    chosen
+}
+
+#[cfg(test)]
+fn sum_of(entries: &[i32], indices: &[usize]) -> i64 {
+    indices.iter().map(|&id| entries[id] as i64).sum()
+}
+
+#[test]
+fn test_find_subset_sum() {
+    let entries = [1721, 979, 366, 299, 675, 1456];
+    let subset = find_subset_sum(&entries, 2020).unwrap().unwrap();
+    assert_eq!(sum_of(&entries, &subset), 2020);
+    assert_eq!(find_subset_sum(&entries, 1).unwrap(), None);
+    assert_eq!(find_subset_sum(&entries, 0).unwrap(), Some(vec![]));
+    assert_eq!(find_subset_sum(&entries, 100_000).unwrap(), None);
+
+    let entries = [3, 5, 7];
+    assert_eq!(find_subset_sum(&entries, 15).unwrap(), Some(vec![0, 1, 2]));
+    assert_eq!(find_subset_sum(&entries, 12).unwrap(), Some(vec![1, 2]));
+    assert_eq!(find_subset_sum(&entries, 11).unwrap(), None);
+}
+
+#[test]
+fn test_find_subset_sum_with_negative_entries() {
+    let entries = [-7, 3, -2, 10, 0];
+    for target in -9..=13 {
+        let subset = find_subset_sum(&entries, target).unwrap();
+        let brute_force = (0..1 << entries.len()).any(|mask: u32| {
+            (0..entries.len())
+                .filter(|id| mask & (1 << id) != 0)
+                .map(|id| entries[id])
+                .sum::<i32>()
+                == target
+        });
+        assert_eq!(subset.is_some(), brute_force, "target {}", target);
+        if let Some(subset) = subset {
+            assert_eq!(sum_of(&entries, &subset), target as i64);
+        }
+    }
+    assert_eq!(find_subset_sum(&entries, -10).unwrap(), None);
+}
+
+#[test]
+fn test_find_closest_subset() {
+    let entries = [1721, 979, 366, 299, 675, 1456];
+    let subset = find_closest_subset(&entries, 2020, 2).unwrap();
+    assert_eq!(subset, [0, 3]);
+    let subset = find_closest_subset(&entries, 2020, 3).unwrap();
+    assert_eq!(subset.len(), 2); // fewer entries win the tie
+    assert_eq!(sum_of(&entries, &subset), 2020);
+
+    // with two entries, 10 + 2 is closest to 13, with three, 10 + 1 + 2 hits it exactly
+    let entries = [10, 1, 2, 50];
+    assert_eq!(find_closest_subset(&entries, 13, 2).unwrap(), [0, 2]);
+    assert_eq!(find_closest_subset(&entries, 13, 3).unwrap(), [0, 1, 2]);
+    assert_eq!(find_closest_subset(&entries, 13, 0).unwrap(), []);
+    assert_eq!(find_closest_subset(&[-5, -4], 1, 5).unwrap(), []);
+    assert_eq!(find_closest_subset(&[-5, -4], -20, 5).unwrap(), [0, 1]);
+}
+
+#[test]
+fn test_subset_sum_table_limit() {
+    assert_eq!(
+        find_subset_sum(&[i32::MAX, i32::MAX], 1),
+        Err(SubsetSumError::TableTooLarge {
+            bytes: table_bytes(2 * i32::MAX as u64 + 1, 1),
+            limit: MAX_TABLE_BYTES
+        })
+    );
+    // 4 bytes of parent and 1/8 byte of bitset per sum, for each of the 3 entry counts
+    assert_eq!(table_bytes(128, 3), 3 * (128 * 4 + 16));
+    // one table row of 2^24 + 1 sums fits, five don't
+    let entries = [1 << 22; 4];
+    assert_eq!(find_subset_sum(&entries, 5), Ok(None));
+    assert_eq!(
+        find_closest_subset(&entries, 5, 4),
+        Err(SubsetSumError::TableTooLarge {
+            bytes: table_bytes((1 << 24) + 1, 5),
+            limit: MAX_TABLE_BYTES
+        })
+    );
+}
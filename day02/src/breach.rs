@@ -0,0 +1,390 @@
+use crate::rules::PasswordRule;
+use memmap2::Mmap;
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// A memory-mapped text file with byte-wise sorted lines, searched by binary search.
+pub struct SortedLines {
+    map: Mmap,
+}
+
+impl SortedLines {
+    /// The file must be sorted byte-wise (e.g. with `LC_ALL=C sort`) and must not change while it's mapped.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<SortedLines> {
+        let file = File::open(path)?;
+        // Safety: the file is only read, and is documented to stay unchanged while mapped.
+        let map = unsafe { Mmap::map(&file)? };
+        Ok(SortedLines { map })
+    }
+
+    /// Finds the line whose key equals `key`. `key_of` extracts the sort key of a line.
+    pub fn find(&self, key: &[u8], key_of: impl Fn(&[u8]) -> &[u8]) -> Option<&[u8]> {
+        let data: &[u8] = &self.map;
+        // `low` always is the start of a line
+        let (mut low, mut high) = (0, data.len());
+        while low < high {
+            let middle = (low + high) / 2;
+            let line_start = data[low..middle]
+                .iter()
+                .rposition(|&b| b == b'\n')
+                .map_or(low, |id| low + id + 1);
+            let line_end = data[middle..]
+                .iter()
+                .position(|&b| b == b'\n')
+                .map_or(data.len(), |id| middle + id);
+            let mut line = &data[line_start..line_end];
+            if line.last() == Some(&b'\r') {
+                line = &line[..line.len() - 1];
+            }
+            match key_of(line).cmp(key) {
+                Ordering::Equal => return Some(line),
+                Ordering::Less => low = line_end + 1,
+                Ordering::Greater => high = line_start,
+            }
+        }
+        None
+    }
+
+    pub fn contains(&self, line: &str) -> bool {
+        self.find(line.as_bytes(), |line| line).is_some()
+    }
+}
+
+/// Probabilistic set: `contains` never misses an inserted item, but may report false positives.
+#[derive(Debug, Clone)]
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    hash_count: u32,
+}
+
+impl BloomFilter {
+    /// Sized for `expected_items` with a false positive rate of about `false_positive_rate`.
+    pub fn new(expected_items: usize, false_positive_rate: f64) -> BloomFilter {
+        let items = expected_items.max(1) as f64;
+        let ln2 = std::f64::consts::LN_2;
+        let bit_count = (-items * false_positive_rate.ln() / (ln2 * ln2))
+            .ceil()
+            .max(64.0);
+        let hash_count = ((bit_count / items) * ln2).round().max(1.0) as u32;
+        BloomFilter {
+            bits: vec![0; (bit_count as usize).div_ceil(64)],
+            hash_count,
+        }
+    }
+
+    /// Builds a filter from the lines of a file.
+    pub fn from_lines<P: AsRef<Path>>(
+        path: P,
+        false_positive_rate: f64,
+    ) -> io::Result<BloomFilter> {
+        let line_count = BufReader::new(File::open(&path)?).lines().count();
+        let mut filter = BloomFilter::new(line_count, false_positive_rate);
+        for line in BufReader::new(File::open(&path)?).lines() {
+            filter.insert(&line?);
+        }
+        Ok(filter)
+    }
+
+    /// One independently seeded hash per bit.
+    fn bit_ids<'a>(&self, item: &'a str) -> impl Iterator<Item = usize> + 'a {
+        let bit_count = self.bits.len() as u64 * 64;
+        (0..self.hash_count as u64).map(move |seed| {
+            let mut hasher = DefaultHasher::new();
+            seed.hash(&mut hasher);
+            item.hash(&mut hasher);
+            (hasher.finish() % bit_count) as usize
+        })
+    }
+
+    pub fn insert(&mut self, item: &str) {
+        let bit_ids: Vec<usize> = self.bit_ids(item).collect();
+        for id in bit_ids {
+            self.bits[id / 64] |= 1 << (id % 64);
+        }
+    }
+
+    pub fn contains(&self, item: &str) -> bool {
+        self.bit_ids(item)
+            .all(|id| self.bits[id / 64] & (1 << (id % 64)) != 0)
+    }
+}
+
+/// Where dictionary words are looked up.
+pub enum WordList {
+    /// Exact lookups in a sorted file.
+    Sorted(SortedLines),
+    /// Fast lookups in memory, with occasional false positives.
+    Bloom(BloomFilter),
+}
+
+impl WordList {
+    pub fn contains(&self, word: &str) -> bool {
+        match self {
+            WordList::Sorted(lines) => lines.contains(word),
+            WordList::Bloom(filter) => filter.contains(word),
+        }
+    }
+}
+
+/// The password must not be a word of the dictionary.
+pub struct NotInDictionary {
+    pub words: WordList,
+}
+
+impl PasswordRule for NotInDictionary {
+    fn describe(&self) -> String {
+        "not a dictionary word".to_string()
+    }
+
+    fn check(&self, password: &str) -> Result<(), String> {
+        match self.words.contains(password) {
+            true => Err("password is a dictionary word".to_string()),
+            false => Ok(()),
+        }
+    }
+}
+
+/// Uppercase hex SHA-1, as used by the breach lists.
+pub fn sha1_hex(password: &str) -> String {
+    sha1_smol::Sha1::from(password)
+        .digest()
+        .to_string()
+        .to_uppercase()
+}
+
+/// Where breached password hashes are looked up.
+pub enum BreachList {
+    /// One file in the format of the offline "Have I Been Pwned" dumps ordered by hash:
+    /// one `<uppercase SHA-1 hex>:<count>` per line.
+    FullHashes(SortedLines),
+    /// A directory in the k-anonymity layout of the "Have I Been Pwned" range API, with one file per
+    /// 5 hex digit hash prefix, named `<PREFIX>.txt` or `<PREFIX>`, listing the sorted `<SUFFIX>:<count>`
+    /// of the remaining 35 hex digits. A missing file means that no hash with that prefix was breached.
+    Prefixes(PathBuf),
+}
+
+/// Length of the hash prefixes that `BreachList::Prefixes` is partitioned by.
+const PREFIX_LENGTH: usize = 5;
+
+fn hash_key(line: &[u8]) -> &[u8] {
+    let end = line.iter().position(|&b| b == b':').unwrap_or(line.len());
+    &line[..end]
+}
+
+/// The count after the colon, 0 if it's missing or invalid.
+fn breach_count_of(line: &[u8]) -> u64 {
+    line.iter()
+        .position(|&b| b == b':')
+        .and_then(|id| {
+            std::str::from_utf8(&line[id + 1..])
+                .ok()?
+                .trim()
+                .parse()
+                .ok()
+        })
+        .unwrap_or(0)
+}
+
+impl BreachList {
+    /// Number of times the uppercase hex SHA-1 occurs in the breaches, `None` if it isn't listed.
+    ///
+    /// Panics if a prefix file exists but can't be read.
+    pub fn breach_count(&self, hash: &str) -> Option<u64> {
+        match self {
+            BreachList::FullHashes(lines) => {
+                lines.find(hash.as_bytes(), hash_key).map(breach_count_of)
+            }
+            BreachList::Prefixes(dir) => {
+                let (prefix, suffix) = hash.split_at(PREFIX_LENGTH);
+                let path = [format!("{}.txt", prefix), prefix.to_string()]
+                    .iter()
+                    .map(|name| dir.join(name))
+                    .find(|path| path.exists())?;
+                let lines = SortedLines::open(&path).unwrap_or_else(|error| {
+                    panic!("can't read breach list {}: {}", path.display(), error)
+                });
+                lines.find(suffix.as_bytes(), hash_key).map(breach_count_of)
+            }
+        }
+    }
+}
+
+/// The password's SHA-1 must not be in a breach list.
+///
+/// The hashes are of the passwords as they were entered, so both the entered and the normalized password are checked.
+pub struct NotBreached {
+    pub hashes: BreachList,
+}
+
+impl NotBreached {
+    /// Number of times the password occurs in the breaches, `None` if it isn't listed.
+    pub fn breach_count(&self, password: &str) -> Option<u64> {
+        self.hashes.breach_count(&sha1_hex(password))
+    }
+}
+
+impl PasswordRule for NotBreached {
+    fn describe(&self) -> String {
+        "not in a known breach".to_string()
+    }
+
+    fn check(&self, password: &str) -> Result<(), String> {
+        match self.breach_count(password) {
+            Some(count) => Err(format!("password found in breach list {} times", count)),
+            None => Ok(()),
+        }
+    }
+
+    fn check_entered(&self, entered: &str, normalized: &str) -> Result<(), String> {
+        self.check(entered)?;
+        match entered == normalized {
+            true => Ok(()),
+            false => self.check(normalized),
+        }
+    }
+}
+
+#[cfg(test)]
+fn temp_file(name: &str, content: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("day02-{}-{}", std::process::id(), name));
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+#[test]
+fn test_sorted_lines() {
+    let path = temp_file("sorted", "apple\nbanana\ncherry\r\ndate\nfig\n");
+    let lines = SortedLines::open(&path).unwrap();
+    for word in &["apple", "banana", "cherry", "date", "fig"] {
+        assert!(lines.contains(word), "{}", word);
+    }
+    for word in &["", "a", "applf", "egg", "zebra", "fig\n"] {
+        assert!(!lines.contains(word), "{}", word);
+    }
+    std::fs::remove_file(path).unwrap();
+
+    let path = temp_file("empty", "");
+    assert!(!SortedLines::open(&path).unwrap().contains("a"));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_bloom_filter() {
+    let mut filter = BloomFilter::new(1000, 0.01);
+    for id in 0..1000 {
+        filter.insert(&format!("word{}", id));
+    }
+    assert!((0..1000).all(|id| filter.contains(&format!("word{}", id))));
+    let false_positives = (0..10000)
+        .filter(|id| filter.contains(&format!("other{}", id)))
+        .count();
+    assert!(false_positives < 300, "{} false positives", false_positives);
+}
+
+#[test]
+fn test_dictionary_rules() {
+    let path = temp_file("dictionary", "letmein\npassword\nqwerty\n");
+    let sorted = NotInDictionary {
+        words: WordList::Sorted(SortedLines::open(&path).unwrap()),
+    };
+    let bloom = NotInDictionary {
+        words: WordList::Bloom(BloomFilter::from_lines(&path, 0.001).unwrap()),
+    };
+    for rule in &[sorted, bloom] {
+        assert_eq!(
+            rule.check("password"),
+            Err("password is a dictionary word".to_string())
+        );
+        assert!(rule.is_satisfied("correcthorse"));
+    }
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_breach_rule() {
+    assert_eq!(
+        sha1_hex("password"),
+        "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8"
+    );
+    let path = temp_file(
+        "breaches",
+        "0000000A0E3B9F25FF41DE4B5AC238C2D545C7A8:15\n\
+         5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8:3861493\n\
+         FFFFFFF8A0382AA9C8D9536EFBA77F261815334D:2\n",
+    );
+    let rule = NotBreached {
+        hashes: BreachList::FullHashes(SortedLines::open(&path).unwrap()),
+    };
+    assert_eq!(rule.breach_count("password"), Some(3861493));
+    assert_eq!(
+        rule.check("password"),
+        Err("password found in breach list 3861493 times".to_string())
+    );
+    assert_eq!(rule.breach_count("correcthorse"), None);
+    assert!(rule.is_satisfied("correcthorse"));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_breach_rule_with_prefixes() {
+    let dir = std::env::temp_dir().join(format!("day02-{}-breach-prefixes", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("5BAA6.txt"),
+        "003D68EB55068C33ACE09247EE4C639306B:3\r\n\
+         1E4C9B93F3F0682250B6CF8331B7EE68FD8:3861493\r\n\
+         FFF983A91443AE72BD98E59ADAC27C3D2C1:1\r\n",
+    )
+    .unwrap();
+    // the same suffix in a file without the extension, for a different prefix
+    std::fs::write(dir.join("00000"), "1E4C9B93F3F0682250B6CF8331B7EE68FD8:9\n").unwrap();
+    let rule = NotBreached {
+        hashes: BreachList::Prefixes(dir.clone()),
+    };
+    assert_eq!(rule.breach_count("password"), Some(3861493));
+    assert_eq!(
+        rule.check("password"),
+        Err("password found in breach list 3861493 times".to_string())
+    );
+    assert_eq!(
+        rule.hashes
+            .breach_count("000001E4C9B93F3F0682250B6CF8331B7EE68FD8"),
+        Some(9)
+    );
+    assert_eq!(
+        rule.hashes
+            .breach_count("5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD9"),
+        None
+    );
+    // no file for the prefix of "correcthorse"
+    assert_eq!(rule.breach_count("correcthorse"), None);
+    assert!(rule.is_satisfied("correcthorse"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_breach_rule_checks_entered_password() {
+    use crate::unicode::ValidationOptions;
+    use crate::PasswordWithPolicy;
+
+    // "café" with a decomposed 'é', which NFC normalization composes
+    let decomposed = "cafe\u{301}";
+    let mut lines = [sha1_hex(decomposed), sha1_hex("secret")];
+    lines.sort();
+    let path = temp_file("breaches-nfd", &format!("{}:7\n{}:2\n", lines[0], lines[1]));
+    let rule = NotBreached {
+        hashes: BreachList::FullHashes(SortedLines::open(&path).unwrap()),
+    };
+    let options = ValidationOptions::default();
+    let entry = PasswordWithPolicy::from_string(&format!("1-3 a: {}", decomposed));
+    assert_eq!(
+        entry.check_with(&rule, &options),
+        Err("password found in breach list 7 times".to_string())
+    );
+    assert!(!entry.is_valid_with(&crate::rules::PolicySet::rule(rule), &options));
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,365 @@
+use crate::report::{NamedRule, ValidationReport};
+use crate::unicode::ValidationOptions;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, BufRead};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Clone)]
+pub struct BulkOptions {
+    pub threads: usize,
+    /// Number of lines handed to a worker at once.
+    pub chunk_size: usize,
+    /// Whether reports are passed on in input order.
+    /// A slow chunk then holds back the later ones, and reading stops until it is done.
+    pub ordered: bool,
+    pub validation: ValidationOptions,
+    /// Checked for every entry in addition to its policy, see `ValidationReport::with_rules`.
+    pub rules: Vec<NamedRule>,
+}
+
+impl Default for BulkOptions {
+    fn default() -> Self {
+        BulkOptions {
+            threads: thread::available_parallelism().map_or(1, |threads| threads.get()),
+            chunk_size: 4096,
+            ordered: true,
+            validation: ValidationOptions::default(),
+            rules: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RuleCounts {
+    pub valid: u64,
+    pub invalid: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BulkSummary {
+    pub lines: u64,
+    /// Counts per rule name, see `ValidationReport::with_rules`.
+    pub rules: HashMap<String, RuleCounts>,
+    pub elapsed: Duration,
+}
+
+impl BulkSummary {
+    fn add(&mut self, report: &ValidationReport) {
+        self.lines += 1;
+        for result in &report.results {
+            let counts = self.rules.entry(result.rule.clone()).or_default();
+            match result.failure {
+                None => counts.valid += 1,
+                Some(_) => counts.invalid += 1,
+            }
+        }
+    }
+
+    fn merge(&mut self, other: BulkSummary) {
+        self.lines += other.lines;
+        for (rule, counts) in other.rules {
+            let total = self.rules.entry(rule).or_default();
+            total.valid += counts.valid;
+            total.invalid += counts.invalid;
+        }
+    }
+
+    pub fn valid_count(&self, rule: &str) -> u64 {
+        self.rules.get(rule).map_or(0, |counts| counts.valid)
+    }
+
+    pub fn lines_per_second(&self) -> f64 {
+        self.lines as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+/// Chunks per thread that may be read but not passed on yet, which bounds the buffered reports.
+const CHUNKS_IN_FLIGHT_PER_THREAD: usize = 4;
+
+struct Chunk {
+    id: usize,
+    /// 1-based line number of the first line.
+    first_line: usize,
+    lines: Vec<String>,
+}
+
+/// Validates every line of the reader on a pool of worker threads.
+///
+/// `on_report` is called on the calling thread for every line, in input order if `options.ordered` is set.
+pub fn validate_parallel<R: BufRead + Send>(
+    reader: R,
+    options: &BulkOptions,
+    mut on_report: impl FnMut(ValidationReport),
+) -> io::Result<BulkSummary> {
+    let start = Instant::now();
+    let threads = options.threads.max(1);
+    let chunk_size = options.chunk_size.max(1);
+    // bounded, so that reading can't get arbitrarily far ahead of validation
+    let (chunk_sender, chunk_receiver) = mpsc::sync_channel::<Chunk>(threads * 2);
+    let chunk_receiver = Arc::new(Mutex::new(chunk_receiver));
+    let (result_sender, result_receiver) =
+        mpsc::sync_channel::<(usize, Vec<ValidationReport>, BulkSummary)>(threads * 2);
+    // one credit per chunk that may be in flight, returned when its reports are passed on
+    let (credit_sender, credit_receiver) = mpsc::channel::<()>();
+    for _ in 0..threads * CHUNKS_IN_FLIGHT_PER_THREAD {
+        credit_sender.send(()).unwrap();
+    }
+
+    let mut summary = BulkSummary::default();
+    let read_result = thread::scope(|scope| {
+        let reading = scope.spawn(move || -> io::Result<()> {
+            let mut lines = reader.lines();
+            let mut first_line = 1;
+            for id in 0.. {
+                if credit_receiver.recv().is_err() {
+                    break; // results aren't passed on anymore
+                }
+                let chunk: Vec<String> =
+                    lines.by_ref().take(chunk_size).collect::<Result<_, _>>()?;
+                if chunk.is_empty() {
+                    break;
+                }
+                let line_count = chunk.len();
+                let chunk = Chunk {
+                    id,
+                    first_line,
+                    lines: chunk,
+                };
+                if chunk_sender.send(chunk).is_err() {
+                    break; // workers are gone
+                }
+                first_line += line_count;
+            }
+            Ok(())
+        });
+
+        for _ in 0..threads {
+            let chunk_receiver = Arc::clone(&chunk_receiver);
+            let result_sender = result_sender.clone();
+            let validation = options.validation;
+            let rules = &options.rules;
+            scope.spawn(move || loop {
+                let chunk = match chunk_receiver.lock().unwrap().recv() {
+                    Ok(chunk) => chunk,
+                    Err(_) => break, // all chunks are read
+                };
+                let mut chunk_summary = BulkSummary::default();
+                let reports: Vec<ValidationReport> = chunk
+                    .lines
+                    .iter()
+                    .enumerate()
+                    .map(|(line_id, entry)| {
+                        let report = ValidationReport::with_rules(
+                            chunk.first_line + line_id,
+                            entry,
+                            &validation,
+                            rules,
+                        );
+                        chunk_summary.add(&report);
+                        report
+                    })
+                    .collect();
+                if result_sender
+                    .send((chunk.id, reports, chunk_summary))
+                    .is_err()
+                {
+                    break;
+                }
+            });
+        }
+        drop(result_sender);
+
+        let mut pending = BTreeMap::new();
+        let mut next_id = 0;
+        for (id, reports, chunk_summary) in result_receiver {
+            summary.merge(chunk_summary);
+            if !options.ordered {
+                reports.into_iter().for_each(&mut on_report);
+                let _ = credit_sender.send(());
+                continue;
+            }
+            pending.insert(id, reports);
+            while let Some(reports) = pending.remove(&next_id) {
+                reports.into_iter().for_each(&mut on_report);
+                let _ = credit_sender.send(());
+                next_id += 1;
+            }
+        }
+        drop(credit_sender);
+        reading.join().unwrap()
+    });
+
+    read_result?;
+    summary.elapsed = start.elapsed();
+    Ok(summary)
+}
+
+#[cfg(test)]
+fn test_input(line_count: usize) -> String {
+    let mut input = String::new();
+    for line in 0..line_count {
+        let c = (b'a' + (line % 3) as u8) as char;
+        input.push_str(&format!("1-3 {}: {}bc{}\n", c, c, "a".repeat(line % 5)));
+    }
+    input.push_str("invalid line\n");
+    input
+}
+
+#[test]
+fn test_parallel_matches_sequential() {
+    let input = test_input(1000);
+    let validation = ValidationOptions::default();
+    let expected: Vec<ValidationReport> = input
+        .lines()
+        .enumerate()
+        .map(|(line_id, entry)| ValidationReport::for_entry(line_id + 1, entry, &validation))
+        .collect();
+
+    let options = BulkOptions {
+        threads: 4,
+        chunk_size: 7,
+        ..BulkOptions::default()
+    };
+    let mut reports = Vec::new();
+    let summary =
+        validate_parallel(input.as_bytes(), &options, |report| reports.push(report)).unwrap();
+    assert_eq!(reports, expected);
+
+    assert_eq!(summary.lines, 1001);
+    let expected_valid = |rule| {
+        expected
+            .iter()
+            .filter(|report| {
+                report
+                    .results
+                    .iter()
+                    .any(|r| r.rule == rule && r.failure.is_none())
+            })
+            .count() as u64
+    };
+    assert_eq!(summary.valid_count("part1"), expected_valid("part1"));
+    assert_eq!(summary.valid_count("part2"), expected_valid("part2"));
+    assert_eq!(
+        summary.rules["parse"],
+        RuleCounts {
+            valid: 0,
+            invalid: 1
+        }
+    );
+    assert_eq!(
+        summary.rules["part1"].valid + summary.rules["part1"].invalid,
+        1000
+    );
+    assert!(summary.lines_per_second() > 0.0);
+}
+
+#[test]
+fn test_ordered_buffering_is_bounded() {
+    use crate::rules::PasswordRule;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Slows down the first chunk, so that all later chunks would pile up behind it.
+    struct SlowFirstLine;
+    impl PasswordRule for SlowFirstLine {
+        fn describe(&self) -> String {
+            "slow".to_string()
+        }
+        fn check(&self, password: &str) -> Result<(), String> {
+            if password == "first" {
+                thread::sleep(Duration::from_millis(200));
+            }
+            Ok(())
+        }
+    }
+
+    /// Counts the lines taken from the input.
+    struct CountingReader<'a> {
+        input: &'a [u8],
+        lines_read: &'a AtomicUsize,
+    }
+    impl io::Read for CountingReader<'_> {
+        fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+            let read = self.input.read(&mut buffer[..1])?;
+            if read == 1 && buffer[0] == b'\n' {
+                self.lines_read.fetch_add(1, Ordering::SeqCst);
+            }
+            Ok(read)
+        }
+    }
+
+    let input = format!("1-3 a: first\n{}", test_input(400));
+    let lines_read = AtomicUsize::new(0);
+    let reader = io::BufReader::with_capacity(
+        1,
+        CountingReader {
+            input: input.as_bytes(),
+            lines_read: &lines_read,
+        },
+    );
+    let options = BulkOptions {
+        threads: 2,
+        chunk_size: 2,
+        rules: vec![("slow".to_string(), Arc::new(SlowFirstLine))],
+        ..BulkOptions::default()
+    };
+    let mut read_at_first_report = None;
+    let mut next_line = 1;
+    let summary = validate_parallel(reader, &options, |report| {
+        read_at_first_report.get_or_insert(lines_read.load(Ordering::SeqCst));
+        assert_eq!(report.line, next_line);
+        next_line += 1;
+    })
+    .unwrap();
+    assert_eq!(summary.lines, 402);
+    let limit = options.threads * CHUNKS_IN_FLIGHT_PER_THREAD * options.chunk_size;
+    assert!(read_at_first_report.unwrap() <= limit);
+}
+
+#[test]
+fn test_parallel_unordered() {
+    let input = test_input(500);
+    let options = BulkOptions {
+        threads: 3,
+        chunk_size: 16,
+        ordered: false,
+        ..BulkOptions::default()
+    };
+    let mut lines = Vec::new();
+    let summary =
+        validate_parallel(input.as_bytes(), &options, |report| lines.push(report.line)).unwrap();
+    lines.sort_unstable();
+    assert_eq!(lines, (1..=501).collect::<Vec<usize>>());
+    assert_eq!(summary.lines, 501);
+
+    let summary = validate_parallel("".as_bytes(), &options, |_| panic!("no lines")).unwrap();
+    assert_eq!(summary.lines, 0);
+}
+
+#[test]
+fn test_parallel_with_additional_rules() {
+    use crate::rules::RegexMatch;
+    use std::sync::Arc;
+
+    let options = BulkOptions {
+        threads: 2,
+        chunk_size: 1,
+        rules: vec![(
+            "digit".to_string(),
+            Arc::new(RegexMatch::new("[0-9]").unwrap()),
+        )],
+        ..BulkOptions::default()
+    };
+    let input = "1-3 a: abc1\n1-3 a: abc\n";
+    let summary = validate_parallel(input.as_bytes(), &options, |_| {}).unwrap();
+    assert_eq!(
+        summary.rules["digit"],
+        RuleCounts {
+            valid: 1,
+            invalid: 1
+        }
+    );
+    assert_eq!(summary.lines, 2);
+}
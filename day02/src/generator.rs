@@ -0,0 +1,515 @@
+use crate::policy::{CharSet, Clause, Policy};
+use crate::rules::PasswordRule;
+use std::collections::HashMap;
+
+/// Which of the two puzzle interpretations of a policy to generate passwords for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Part {
+    /// Occurrence counts, see `Policy::part1_rule`.
+    Part1,
+    /// Positions, see `Policy::part2_rule`.
+    Part2,
+}
+
+/// SplitMix64, small and good enough for reproducible test data.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn seeded(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..bound`. `bound` must not be 0.
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    /// Uniform in `min..=max`.
+    pub fn between(&mut self, min: usize, max: usize) -> usize {
+        min + self.below(max - min + 1)
+    }
+
+    fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        match items.is_empty() {
+            true => None,
+            false => Some(&items[self.below(items.len())]),
+        }
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for id in (1..items.len()).rev() {
+            items.swap(id, self.below(id + 1));
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GeneratorConfig {
+    pub length: usize,
+    /// The chars passwords are made of. They should each be a grapheme of their own, i.e. no combining marks.
+    pub alphabet: Vec<char>,
+    /// How many random constructions to try before giving up.
+    pub max_attempts: usize,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        GeneratorConfig {
+            length: 12,
+            alphabet: ('a'..='z').collect(),
+            max_attempts: 100,
+        }
+    }
+}
+
+/// Generates passwords that satisfy or deliberately violate the part 1 or part 2 rules of a policy.
+pub struct PasswordGenerator {
+    pub config: GeneratorConfig,
+    rng: Rng,
+}
+
+impl PasswordGenerator {
+    pub fn new(config: GeneratorConfig, seed: u64) -> PasswordGenerator {
+        PasswordGenerator {
+            config,
+            rng: Rng::seeded(seed),
+        }
+    }
+
+    /// Returns `None` if no fitting password was found, e.g. because the length is too short for the policy.
+    ///
+    /// Each clause is constructed on its own and the result is checked against the whole policy,
+    /// so policies with overlapping char sets may need several attempts.
+    pub fn generate(&mut self, policy: &Policy, part: Part, valid: bool) -> Option<String> {
+        if policy.clauses.is_empty() {
+            // every password is valid, none is invalid
+            return match valid {
+                true => self.random_password(),
+                false => None,
+            };
+        }
+        let rule = match part {
+            Part::Part1 => policy.part1_rule(),
+            Part::Part2 => policy.part2_rule(),
+        };
+        for _ in 0..self.config.max_attempts {
+            let candidate = match part {
+                Part::Part1 => self.construct_part1(policy, valid),
+                Part::Part2 => self.construct_part2(policy, valid),
+            };
+            if let Some(candidate) = candidate {
+                let password: String = candidate.into_iter().collect();
+                if rule.is_satisfied(&password) == valid {
+                    return Some(password);
+                }
+            }
+        }
+        None
+    }
+
+    /// Any chars of the alphabet, `None` if there are none.
+    fn random_password(&mut self) -> Option<String> {
+        let alphabet = self.config.alphabet.clone();
+        (0..self.config.length)
+            .map(|_| self.rng.choose(&alphabet).cloned())
+            .collect()
+    }
+
+    fn chars_in(&self, chars: &CharSet) -> Vec<char> {
+        self.config
+            .alphabet
+            .iter()
+            .cloned()
+            .filter(|&c| chars.contains(c))
+            .collect()
+    }
+
+    /// Chars that don't belong to any of the clauses.
+    fn free_chars(&self, policy: &Policy) -> Vec<char> {
+        self.config
+            .alphabet
+            .iter()
+            .cloned()
+            .filter(|&c| !policy.clauses.iter().any(|clause| clause.chars.contains(c)))
+            .collect()
+    }
+
+    /// A random char outside of the clause's set, preferably one that's in no set at all.
+    fn other_char(&mut self, clause: &Clause, free: &[char]) -> Option<char> {
+        if let Some(&c) = self.rng.choose(free) {
+            return Some(c);
+        }
+        let others: Vec<char> = self
+            .config
+            .alphabet
+            .iter()
+            .cloned()
+            .filter(|&c| !clause.chars.contains(c))
+            .collect();
+        self.rng.choose(&others).cloned()
+    }
+
+    fn construct_part1(&mut self, policy: &Policy, valid: bool) -> Option<Vec<char>> {
+        let length = self.config.length;
+        let free = self.free_chars(policy);
+        let mut password: Vec<Option<char>> = vec![None; length];
+        let mut open_positions: Vec<usize> = (0..length).collect();
+        self.rng.shuffle(&mut open_positions);
+
+        let violated = match valid {
+            true => None,
+            false => Some(self.rng.below(policy.clauses.len())),
+        };
+        for (clause_id, clause) in policy.clauses.iter().enumerate() {
+            let min = clause.min.unwrap_or(0);
+            let max = clause.max.unwrap_or(usize::MAX).min(open_positions.len());
+            let count = if violated == Some(clause_id) {
+                // too few or too many, whatever is possible
+                let mut counts = Vec::new();
+                if min > 0 {
+                    counts.push(self.rng.below(min));
+                }
+                if let Some(clause_max) = clause.max {
+                    if clause_max < open_positions.len() {
+                        counts.push(self.rng.between(clause_max + 1, open_positions.len()));
+                    }
+                }
+                *self.rng.choose(&counts)?
+            } else {
+                if min > max {
+                    return None;
+                }
+                self.rng.between(min, max)
+            };
+            let set_chars = self.chars_in(&clause.chars);
+            for _ in 0..count {
+                let position = open_positions.pop()?;
+                password[position] = Some(*self.rng.choose(&set_chars)?);
+            }
+        }
+
+        let fill_clause = &policy.clauses[0];
+        password
+            .into_iter()
+            .map(|c| match c {
+                Some(c) => Some(c),
+                None => self.other_char(fill_clause, &free),
+            })
+            .collect()
+    }
+
+    fn construct_part2(&mut self, policy: &Policy, valid: bool) -> Option<Vec<char>> {
+        let length = self.config.length;
+        let free = self.free_chars(policy);
+        let mut password: Vec<Option<char>> = vec![None; length];
+
+        let violated = match valid {
+            true => None,
+            false => Some(self.rng.below(policy.clauses.len())),
+        };
+        for (clause_id, clause) in policy.clauses.iter().enumerate() {
+            let positions = [clause.min.unwrap_or(0), clause.max.unwrap_or(0)];
+            // which of the positions get a char of the set
+            let at = match (violated == Some(clause_id), self.rng.below(2)) {
+                (false, first) => [first == 0, first != 0],
+                (true, both) => [both == 0, both == 0],
+            };
+            let set_chars = self.chars_in(&clause.chars);
+            for (&position, &in_set) in positions.iter().zip(at.iter()) {
+                if position == 0 || position > length {
+                    if in_set {
+                        return None; // can't put a char there
+                    }
+                    continue;
+                }
+                let c = match in_set {
+                    true => *self.rng.choose(&set_chars)?,
+                    false => self.other_char(clause, &free)?,
+                };
+                password[position - 1] = Some(c);
+            }
+        }
+
+        let alphabet = self.config.alphabet.clone();
+        password
+            .into_iter()
+            .map(|c| c.or_else(|| self.rng.choose(&alphabet).cloned()))
+            .collect()
+    }
+}
+
+/// Groups the alphabet by which clauses contain a char: (membership per clause, number of chars).
+fn atoms(policy: &Policy, alphabet: &[char]) -> Vec<(Vec<bool>, u128)> {
+    let mut atoms = HashMap::<Vec<bool>, u128>::new();
+    for &c in alphabet {
+        let membership = policy
+            .clauses
+            .iter()
+            .map(|clause| clause.chars.contains(c))
+            .collect();
+        *atoms.entry(membership).or_default() += 1;
+    }
+    atoms.into_iter().collect()
+}
+
+/// Number of passwords of `length` chars over the alphabet that satisfy the policy's rules.
+/// The alphabet is deduplicated. Returns `None` if the number doesn't fit into an `u128`.
+pub fn count_valid(policy: &Policy, part: Part, length: usize, alphabet: &[char]) -> Option<u128> {
+    let mut alphabet = alphabet.to_vec();
+    alphabet.sort_unstable();
+    alphabet.dedup();
+    if alphabet.is_empty() && length > 0 {
+        return Some(0);
+    }
+    let atoms = atoms(policy, &alphabet);
+    match part {
+        Part::Part1 => count_part1(policy, length, &atoms),
+        Part::Part2 => count_part2(policy, length, &atoms, alphabet.len() as u128),
+    }
+}
+
+/// DP over the positions, with the state being the occurrence count per clause.
+/// Counts are capped at the point where more occurrences don't change the outcome anymore.
+fn count_part1(policy: &Policy, length: usize, atoms: &[(Vec<bool>, u128)]) -> Option<u128> {
+    let caps: Vec<usize> = policy
+        .clauses
+        .iter()
+        .map(|clause| match clause.max {
+            Some(max) => max.min(length) + 1,
+            None => clause.min.unwrap_or(0).min(length + 1),
+        })
+        .collect();
+
+    let mut states = HashMap::<Vec<usize>, u128>::new();
+    states.insert(vec![0; caps.len()], 1);
+    for _ in 0..length {
+        let mut next_states = HashMap::new();
+        for (counts, ways) in &states {
+            for (membership, atom_size) in atoms {
+                let next_counts: Vec<usize> = counts
+                    .iter()
+                    .zip(membership)
+                    .zip(&caps)
+                    .map(|((&count, &member), &cap)| (count + member as usize).min(cap))
+                    .collect();
+                let next_ways: &mut u128 = next_states.entry(next_counts).or_default();
+                *next_ways = next_ways.checked_add(ways.checked_mul(*atom_size)?)?;
+            }
+        }
+        states = next_states;
+    }
+
+    let is_valid = |counts: &[usize]| {
+        policy.clauses.iter().zip(counts).all(|(clause, &count)| {
+            clause.min.unwrap_or(0) <= count && clause.max.is_none_or(|max| count <= max)
+        })
+    };
+    states
+        .iter()
+        .filter(|(counts, _)| is_valid(counts))
+        .try_fold(0u128, |total, (_, &ways)| total.checked_add(ways))
+}
+
+/// Enumerates which atom is at each of the constrained positions; all other positions are arbitrary.
+fn count_part2(
+    policy: &Policy,
+    length: usize,
+    atoms: &[(Vec<bool>, u128)],
+    alphabet_size: u128,
+) -> Option<u128> {
+    let mut positions: Vec<usize> = policy
+        .clauses
+        .iter()
+        .flat_map(|clause| vec![clause.min.unwrap_or(0), clause.max.unwrap_or(0)])
+        .filter(|&position| position > 0 && position <= length)
+        .collect();
+    positions.sort_unstable();
+    positions.dedup();
+
+    let mut total = 0u128;
+    let mut choice = vec![0; positions.len()];
+    loop {
+        let is_at = |clause_id: usize, position: usize| {
+            positions
+                .iter()
+                .position(|&p| p == position)
+                .is_some_and(|id| atoms[choice[id]].0[clause_id])
+        };
+        let is_valid = policy
+            .clauses
+            .iter()
+            .enumerate()
+            .all(|(clause_id, clause)| {
+                is_at(clause_id, clause.min.unwrap_or(0))
+                    != is_at(clause_id, clause.max.unwrap_or(0))
+            });
+        if is_valid {
+            let ways = choice
+                .iter()
+                .try_fold(1u128, |ways, &atom_id| ways.checked_mul(atoms[atom_id].1))?;
+            total = total.checked_add(ways)?;
+        }
+
+        // next combination, like counting in base atoms.len()
+        let mut id = 0;
+        loop {
+            if id == choice.len() {
+                let free_positions = (length - positions.len()) as u32;
+                return total.checked_mul(alphabet_size.checked_pow(free_positions)?);
+            }
+            choice[id] += 1;
+            if choice[id] < atoms.len() {
+                break;
+            }
+            choice[id] = 0;
+            id += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+fn brute_force_count(policy: &Policy, part: Part, length: usize, alphabet: &[char]) -> u128 {
+    let rule = match part {
+        Part::Part1 => policy.part1_rule(),
+        Part::Part2 => policy.part2_rule(),
+    };
+    let mut count = 0;
+    for mut id in 0..alphabet.len().pow(length as u32) {
+        let mut password = String::new();
+        for _ in 0..length {
+            password.push(alphabet[id % alphabet.len()]);
+            id /= alphabet.len();
+        }
+        if rule.is_satisfied(&password) {
+            count += 1;
+        }
+    }
+    count
+}
+
+#[test]
+fn test_rng_is_reproducible() {
+    let mut a = Rng::seeded(42);
+    let mut b = Rng::seeded(42);
+    let mut c = Rng::seeded(43);
+    let a: Vec<u64> = (0..5).map(|_| a.next_u64()).collect();
+    let b: Vec<u64> = (0..5).map(|_| b.next_u64()).collect();
+    let c: Vec<u64> = (0..5).map(|_| c.next_u64()).collect();
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+}
+
+#[test]
+fn test_generate() {
+    let config = GeneratorConfig {
+        length: 8,
+        alphabet: "abcdef0123".chars().collect(),
+        max_attempts: 100,
+    };
+    let mut generator = PasswordGenerator::new(config.clone(), 7);
+    for policy in &["1-3 a", "2-5 b", "1-3 a,b; 2- [0-9]", "-1 c; 3-4 d"] {
+        let policy: Policy = policy.parse().unwrap();
+        for &part in &[Part::Part1, Part::Part2] {
+            let rule = match part {
+                Part::Part1 => policy.part1_rule(),
+                Part::Part2 => policy.part2_rule(),
+            };
+            for _ in 0..20 {
+                let password = generator.generate(&policy, part, true).unwrap();
+                assert_eq!(password.chars().count(), 8);
+                assert!(password.chars().all(|c| config.alphabet.contains(&c)));
+                assert!(rule.is_satisfied(&password), "{} {}", policy, password);
+
+                let password = generator.generate(&policy, part, false).unwrap();
+                assert!(!rule.is_satisfied(&password), "{} {}", policy, password);
+            }
+        }
+    }
+
+    let mut first = PasswordGenerator::new(config.clone(), 1);
+    let mut second = PasswordGenerator::new(config, 1);
+    let policy: Policy = "1-3 a".parse().unwrap();
+    assert_eq!(
+        first.generate(&policy, Part::Part1, true),
+        second.generate(&policy, Part::Part1, true)
+    );
+}
+
+#[test]
+fn test_generate_impossible() {
+    let mut generator = PasswordGenerator::new(
+        GeneratorConfig {
+            length: 3,
+            ..GeneratorConfig::default()
+        },
+        0,
+    );
+    let policy: Policy = "5-6 a".parse().unwrap();
+    assert_eq!(generator.generate(&policy, Part::Part1, true), None);
+    assert_eq!(generator.generate(&policy, Part::Part2, true), None);
+    assert!(generator.generate(&policy, Part::Part2, false).is_some());
+
+    let no_clauses = Policy { clauses: vec![] };
+    for &part in &[Part::Part1, Part::Part2] {
+        let password = generator.generate(&no_clauses, part, true).unwrap();
+        assert_eq!(password.len(), 3);
+        assert_eq!(generator.generate(&no_clauses, part, false), None);
+    }
+}
+
+#[test]
+fn test_count_valid() {
+    let alphabet: Vec<char> = "abc".chars().collect();
+    // sum over c in 1..=3 of C(5, c) * 2^(5 - c) = 80 + 80 + 40
+    let policy: Policy = "1-3 a".parse().unwrap();
+    assert_eq!(count_valid(&policy, Part::Part1, 5, &alphabet), Some(200));
+    // 2 * 1 * 2 * 3^3
+    assert_eq!(count_valid(&policy, Part::Part2, 5, &alphabet), Some(108));
+
+    for policy in &["1-2 a,b; 2- b", "-1 a; 1-3 [b-c]", "2-2 a; 1-4 a", "0-7 a"] {
+        let policy: Policy = policy.parse().unwrap();
+        for &part in &[Part::Part1, Part::Part2] {
+            assert_eq!(
+                count_valid(&policy, part, 5, &alphabet),
+                Some(brute_force_count(&policy, part, 5, &alphabet)),
+                "{} {:?}",
+                policy,
+                part
+            );
+        }
+    }
+
+    let alphabet: Vec<char> = ('a'..='z').collect();
+    assert_eq!(
+        count_valid(&policy_of("0-100 a"), Part::Part1, 27, &alphabet),
+        Some(26u128.pow(27))
+    );
+    // without chars, only the empty password exists
+    for &part in &[Part::Part1, Part::Part2] {
+        assert_eq!(count_valid(&policy_of("1-3 a"), part, 3, &[]), Some(0));
+        assert_eq!(
+            count_valid(&policy_of("0-3 a"), part, 0, &[]),
+            Some(brute_force_count(&policy_of("0-3 a"), part, 0, &[]))
+        );
+    }
+    assert_eq!(
+        count_valid(&policy_of("0-100 a"), Part::Part1, 28, &alphabet),
+        None
+    );
+}
+
+#[cfg(test)]
+fn policy_of(policy: &str) -> Policy {
+    policy.parse().unwrap()
+}
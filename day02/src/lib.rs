@@ -0,0 +1,214 @@
+pub mod breach;
+pub mod bulk;
+pub mod generator;
+pub mod policy;
+pub mod report;
+pub mod rules;
+pub mod unicode;
+
+use policy::{Policy, PolicyParseError};
+use rules::PasswordRule;
+use std::str::FromStr;
+use unicode::ValidationOptions;
+
+type Password = String;
+
+#[derive(Debug, PartialEq)]
+pub struct PasswordWithPolicy {
+    policy: Policy,
+    password: Password,
+}
+
+impl FromStr for PasswordWithPolicy {
+    type Err = PolicyParseError;
+
+    /// Expects `<policy>: <password>`. Error spans refer to the whole string.
+    fn from_str(password_with_policy_string: &str) -> Result<Self, Self::Err> {
+        let separator = password_with_policy_string
+            .find(": ")
+            .ok_or_else(|| PolicyParseError {
+                span: password_with_policy_string.len()..password_with_policy_string.len(),
+                message: "expected ': ' followed by the password".to_string(),
+            })?;
+        Ok(PasswordWithPolicy {
+            policy: password_with_policy_string[..separator].parse()?,
+            password: password_with_policy_string[separator + 2..].to_string(),
+        })
+    }
+}
+
+impl PasswordWithPolicy {
+    /// Panics on invalid input, use `parse` to handle errors.
+    pub fn from_string(password_with_policy_string: &str) -> PasswordWithPolicy {
+        match password_with_policy_string.parse() {
+            Ok(password_with_policy) => password_with_policy,
+            Err(e) => panic!("{}", e.annotate(password_with_policy_string)),
+        }
+    }
+
+    /// Checks the password against an arbitrary rule, ignoring the own policy.
+    pub fn is_valid(&self, rule: &dyn PasswordRule) -> bool {
+        self.is_valid_with(rule, &ValidationOptions::default())
+    }
+
+    /// Like `is_valid`, but the password is normalized according to the options first.
+    pub fn is_valid_with(&self, rule: &dyn PasswordRule, options: &ValidationOptions) -> bool {
+        self.check_with(rule, options).is_ok()
+    }
+
+    /// Like `is_valid_with`, but returns the reason why the password is invalid.
+    /// The rule gets the password as entered, too, see `PasswordRule::check_entered`.
+    pub fn check_with(
+        &self,
+        rule: &dyn PasswordRule,
+        options: &ValidationOptions,
+    ) -> Result<(), String> {
+        rule.check_entered(&self.password, &options.normalization.apply(&self.password))
+    }
+
+    pub fn is_valid_part2(&self) -> bool {
+        self.is_valid_part2_with(&ValidationOptions::default())
+    }
+
+    pub fn is_valid_part2_with(&self, options: &ValidationOptions) -> bool {
+        self.check_part2_with(options).is_ok()
+    }
+
+    pub fn check_part2_with(&self, options: &ValidationOptions) -> Result<(), String> {
+        let policy = self.policy.normalized(options.normalization);
+        self.check_with(&policy.part2_rule(), options)
+    }
+
+    pub fn is_valid_part1(&self) -> bool {
+        self.is_valid_part1_with(&ValidationOptions::default())
+    }
+
+    pub fn is_valid_part1_with(&self, options: &ValidationOptions) -> bool {
+        self.check_part1_with(options).is_ok()
+    }
+
+    pub fn check_part1_with(&self, options: &ValidationOptions) -> Result<(), String> {
+        let policy = self.policy.normalized(options.normalization);
+        self.check_with(&policy.part1_rule(), options)
+    }
+
+    pub fn policy(&self) -> &Policy {
+        &self.policy
+    }
+
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+}
+
+#[test]
+fn test_part1_simple_example() {
+    let test_strings = ["1-3 a: abcde", "1-3 b: cdefg", "2-9 c: ccccccccc"];
+    let p0 = PasswordWithPolicy::from_string(test_strings[0]);
+    let p1 = PasswordWithPolicy::from_string(test_strings[1]);
+    let p2 = PasswordWithPolicy::from_string(test_strings[2]);
+    assert!(p0.is_valid_part1());
+    assert!(!p1.is_valid_part1());
+    assert!(p2.is_valid_part1());
+}
+
+#[test]
+fn test_part2_simple_example() {
+    let test_strings = ["1-3 a: abcde", "1-3 b: cdefg", "2-9 c: ccccccccc"];
+    let p0 = PasswordWithPolicy::from_string(test_strings[0]);
+    let p1 = PasswordWithPolicy::from_string(test_strings[1]);
+    let p2 = PasswordWithPolicy::from_string(test_strings[2]);
+    assert!(p0.is_valid_part2());
+    assert!(!p1.is_valid_part2());
+    assert!(!p2.is_valid_part2());
+}
+
+#[test]
+fn test_unicode_validation() {
+    use unicode::Normalization;
+
+    // policy with a precomposed 'é', password with a decomposed one
+    let pw = PasswordWithPolicy::from_string("1-2 \u{e9}: e\u{301}x");
+    assert!(pw.is_valid_part1());
+    assert!(pw.is_valid_part2());
+    let options = ValidationOptions {
+        normalization: Normalization::None,
+    };
+    assert!(!pw.is_valid_part1_with(&options));
+    assert!(!pw.is_valid_part2_with(&options));
+
+    // NFKC decomposes the ligature into "fi"
+    let pw = PasswordWithPolicy::from_string("1-1 i: \u{fb01}");
+    assert!(!pw.is_valid_part1());
+    let options = ValidationOptions {
+        normalization: Normalization::Nfkc,
+    };
+    assert!(pw.is_valid_part1_with(&options));
+
+    // positions out of range are invalid, instead of panicking
+    let pw = PasswordWithPolicy::from_string("7-9 a: abc");
+    assert!(!pw.is_valid_part2());
+    let pw = PasswordWithPolicy::from_string("0-1 a: abc");
+    assert!(pw.is_valid_part2());
+}
+
+#[cfg(test)]
+fn simple_policy(required_char: char, required_min: usize, required_max: usize) -> Policy {
+    Policy {
+        clauses: vec![policy::Clause {
+            min: Some(required_min),
+            max: Some(required_max),
+            chars: policy::CharSet::single(required_char),
+        }],
+    }
+}
+
+#[test]
+fn test_policy_from_string() {
+    let p: Policy = "1-3 a".parse().unwrap();
+    assert_eq!(p, simple_policy('a', 1, 3));
+}
+
+#[test]
+fn test_password_with_policy_from_string() {
+    let password_with_policy = PasswordWithPolicy::from_string("1-3 a: abcde");
+    assert_eq!(
+        password_with_policy,
+        PasswordWithPolicy {
+            policy: simple_policy('a', 1, 3),
+            password: "abcde".to_string()
+        }
+    );
+
+    let password_with_policy = PasswordWithPolicy::from_string("1-3 b: cdefg");
+    assert_eq!(
+        password_with_policy,
+        PasswordWithPolicy {
+            policy: simple_policy('b', 1, 3),
+            password: "cdefg".to_string()
+        }
+    );
+
+    let password_with_policy = PasswordWithPolicy::from_string("2-9 c: ccccccccc");
+    assert_eq!(
+        password_with_policy,
+        PasswordWithPolicy {
+            policy: simple_policy('c', 2, 9),
+            password: "ccccccccc".to_string()
+        }
+    );
+}
+
+#[test]
+fn test_password_with_policy_parse_errors() {
+    let error = "1-3 a abcde".parse::<PasswordWithPolicy>().unwrap_err();
+    assert_eq!(error.span, 11..11);
+    let error = "1-3 ; x: abcde".parse::<PasswordWithPolicy>().unwrap_err();
+    assert_eq!(error.span, 4..5);
+    assert_eq!(error.message, "expected a char, found ';'");
+
+    let password_with_policy = PasswordWithPolicy::from_string("1-2 a,b; -0 x: abx");
+    assert!(!password_with_policy.is_valid_part1());
+    let password_with_policy = PasswordWithPolicy::from_string("1-2 a,b; -0 x: ab");
+    assert!(password_with_policy.is_valid_part1());
+}
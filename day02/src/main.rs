@@ -1,72 +1,206 @@
+use day02::breach::{BloomFilter, BreachList, NotBreached, NotInDictionary, SortedLines, WordList};
+use day02::bulk::{validate_parallel, BulkOptions};
+use day02::generator::{count_valid, GeneratorConfig, Part, PasswordGenerator};
+use day02::policy::Policy;
+use day02::report::{write_report, NamedRule, ReportFormat, ValidationReport, CSV_HEADER};
+use day02::unicode::ValidationOptions;
+use day02::PasswordWithPolicy;
+use std::env;
 use std::fs::File;
-use std::io::{self, BufRead};
-
-type Password = String;
-
-#[derive(Debug, PartialEq)]
-struct Policy {
-    required_char: char,
-    required_min: usize,
-    required_max: usize,
+use std::io::{self, BufRead, Write};
+use std::sync::Arc;
+
+/// Removes `--dictionary <file>`, `--bloom-dictionary <file>`, `--breaches <file>` and
+/// `--breach-prefixes <dir>` from the args and returns the rules they stand for.
+fn take_rule_args(args: &mut Vec<String>) -> Vec<NamedRule> {
+    let mut rules: Vec<NamedRule> = Vec::new();
+    while let Some(flag_id) = args.iter().position(|arg| arg.starts_with("--")) {
+        let flag = args.remove(flag_id);
+        assert!(flag_id < args.len(), "missing file for {}", flag);
+        let path = args.remove(flag_id);
+        match flag.as_str() {
+            "--dictionary" => rules.push((
+                "dictionary".to_string(),
+                Arc::new(NotInDictionary {
+                    words: WordList::Sorted(SortedLines::open(&path).unwrap()),
+                }),
+            )),
+            "--bloom-dictionary" => rules.push((
+                "dictionary".to_string(),
+                Arc::new(NotInDictionary {
+                    words: WordList::Bloom(BloomFilter::from_lines(&path, 0.001).unwrap()),
+                }),
+            )),
+            "--breaches" => rules.push((
+                "breach".to_string(),
+                Arc::new(NotBreached {
+                    hashes: BreachList::FullHashes(SortedLines::open(&path).unwrap()),
+                }),
+            )),
+            "--breach-prefixes" => rules.push((
+                "breach".to_string(),
+                Arc::new(NotBreached {
+                    hashes: BreachList::Prefixes(path.into()),
+                }),
+            )),
+            _ => panic!("unknown option {}", flag),
+        }
+    }
+    rules
 }
 
-#[derive(Debug, PartialEq)]
-pub struct PasswordWithPolicy {
-    policy: Policy,
-    password: Password,
+/// Usage: `day02 report [text|json|csv] [--dictionary <file>] [--bloom-dictionary <file>] [--breaches <file>]
+/// [--breach-prefixes <dir>]`
+///
+/// Writes a report for every line, stating why entries were rejected.
+fn print_reports(reader: impl BufRead, format: ReportFormat, rules: &[NamedRule]) {
+    let options = ValidationOptions::default();
+    let stdout = io::stdout();
+    let mut out = io::BufWriter::new(stdout.lock());
+    if format == ReportFormat::Csv {
+        writeln!(out, "{}", CSV_HEADER).unwrap();
+    }
+    for (line_id, entry) in reader.lines().enumerate() {
+        let report = ValidationReport::with_rules(line_id + 1, &entry.unwrap(), &options, rules);
+        write_report(&mut out, &report, format).unwrap();
+    }
 }
 
-impl PasswordWithPolicy {
-    pub fn from_string(password_with_policy_string: &str) -> PasswordWithPolicy {
-        let mut pw_with_pol_string_parts = password_with_policy_string.split(": ");
-        PasswordWithPolicy {
-            policy: Policy::from_string(pw_with_pol_string_parts.next().unwrap()),
-            password: pw_with_pol_string_parts.next().unwrap().to_string(),
+/// Usage: `day02 bulk [threads] [ordered|unordered] [text|json|csv]`, with the options of `report`
+///
+/// Validates on multiple threads and prints counts per rule and the throughput.
+/// Reports are only written if a format is given.
+fn print_bulk(reader: io::BufReader<File>, args: &[String], rules: Vec<NamedRule>) {
+    let mut options = BulkOptions {
+        rules,
+        ..BulkOptions::default()
+    };
+    if let Some(threads) = args.first() {
+        options.threads = threads.parse().expect("invalid thread count");
+    }
+    options.ordered = args.get(1).map(|order| order.as_str()) != Some("unordered");
+    let format = args
+        .get(2)
+        .map(|format| format.parse::<ReportFormat>().unwrap());
+
+    let stdout = io::stdout();
+    let mut out = io::BufWriter::new(stdout.lock());
+    if format == Some(ReportFormat::Csv) {
+        writeln!(out, "{}", CSV_HEADER).unwrap();
+    }
+    let summary = validate_parallel(reader, &options, |report| {
+        if let Some(format) = format {
+            write_report(&mut out, &report, format).unwrap();
         }
+    })
+    .unwrap();
+    drop(out);
+
+    let mut rules: Vec<_> = summary.rules.iter().collect();
+    rules.sort_by_key(|(rule, _)| rule.as_str());
+    for (rule, counts) in rules {
+        println!(
+            "{}: {} valid, {} invalid",
+            rule, counts.valid, counts.invalid
+        );
     }
+    println!(
+        "{} lines on {} threads in {:?} ({:.0} lines/s)",
+        summary.lines,
+        options.threads,
+        summary.elapsed,
+        summary.lines_per_second()
+    );
+}
 
-    pub fn is_valid_part2(&self) -> bool {
-        let chars = self.password.chars().collect::<Vec<char>>();
-        let req_char_at_min = chars[self.policy.required_min - 1] == self.policy.required_char;
-        let req_char_at_max = chars[self.policy.required_max - 1] == self.policy.required_char;
-        //println!("at chars[{}]: {:?} -> valid = {}", self.policy.required_min - 1, chars[self.policy.required_min - 1], valid);
-        //println!("at chars[{}]: {:?} -> valid = {}", self.policy.required_max - 1, chars[self.policy.required_max - 1], valid);
-
-        return (!req_char_at_max && req_char_at_min) || (req_char_at_max && !req_char_at_min)
+fn parse_part(part: &str) -> Part {
+    match part {
+        "part1" => Part::Part1,
+        "part2" => Part::Part2,
+        _ => panic!("unknown part {:?}", part),
     }
+}
 
-    pub fn is_valid_part1(&self) -> bool {
-        let occurrence_count = self
-            .password
-            .split(self.policy.required_char)
-            .collect::<Vec<&str>>()
-            .len()
-            - 1; // -1, because the one element will always exist even if there's no occurrence of the required_char
-        return self.policy.required_min <= occurrence_count
-            && occurrence_count <= self.policy.required_max;
+/// Usage: `day02 generate <part1|part2> <valid|invalid> <policy> <count> [length] [seed]`
+///
+/// Prints `<policy>: <password>` lines, in the format of the puzzle input.
+fn print_generated(args: &[String]) {
+    let part = parse_part(&args[0]);
+    let valid = args[1] == "valid";
+    let policy: Policy = args[2].parse().unwrap();
+    let count: usize = args[3].parse().expect("invalid count");
+    let mut config = GeneratorConfig::default();
+    if let Some(length) = args.get(4) {
+        config.length = length.parse().expect("invalid length");
+    }
+    let seed = args
+        .get(5)
+        .map_or(0, |seed| seed.parse().expect("invalid seed"));
+
+    let mut generator = PasswordGenerator::new(config, seed);
+    for _ in 0..count {
+        match generator.generate(&policy, part, valid) {
+            Some(password) => println!("{}: {}", policy, password),
+            None => {
+                println!("Can't generate a fitting password for {}", policy);
+                return;
+            }
+        }
     }
 }
 
-impl Policy {
-    fn from_string(policy_string: &str) -> Policy {
-        let mut policy_parts = policy_string.split(' ');
-        let mut required_range = policy_parts.next().unwrap().split("-");
-        Policy {
-            required_char: policy_parts.next().unwrap().parse().unwrap(),
-            required_min: required_range.next().unwrap().parse().unwrap(),
-            required_max: required_range.next().unwrap().parse().unwrap(),
+/// Usage: `day02 count <policy> <length>`
+///
+/// Number of valid passwords of the given length over the default alphabet a-z.
+fn print_counts(args: &[String]) {
+    let policy: Policy = args[0].parse().unwrap();
+    let length: usize = args[1].parse().expect("invalid length");
+    let alphabet = GeneratorConfig::default().alphabet;
+    for &(name, part) in &[("Part 1", Part::Part1), ("Part 2", Part::Part2)] {
+        match count_valid(&policy, part, length, &alphabet) {
+            Some(count) => println!("{} valid passwords: {}", name, count),
+            None => println!("{} valid passwords: more than {}", name, u128::MAX),
         }
     }
 }
 
+/// Only the modes that validate entries read the input.
+fn open_input() -> io::BufReader<File> {
+    io::BufReader::new(File::open("input.txt").unwrap())
+}
+
 fn main() {
-    let file = File::open("input.txt").unwrap();
-    let reader = io::BufReader::new(file);
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(|mode| mode.as_str()) {
+        Some("report") => {
+            let rules = take_rule_args(&mut args);
+            let format = match args.get(1) {
+                Some(format) => format.parse().unwrap(),
+                None => ReportFormat::Text,
+            };
+            print_reports(open_input(), format, &rules);
+            return;
+        }
+        Some("generate") => {
+            print_generated(&args[1..]);
+            return;
+        }
+        Some("count") => {
+            print_counts(&args[1..]);
+            return;
+        }
+        Some("bulk") => {
+            let rules = take_rule_args(&mut args);
+            print_bulk(open_input(), &args[1..], rules);
+            return;
+        }
+        _ => (),
+    }
 
     let mut valid_count_part1 = 0;
     let mut valid_count_part2 = 0;
 
-    for entry in reader.lines() {
+    for entry in open_input().lines() {
         let pw = PasswordWithPolicy::from_string(&entry.unwrap());
         if pw.is_valid_part1() {
             valid_count_part1 += 1;
@@ -78,80 +212,3 @@ fn main() {
     println!("Part 1 valid passwords: {}", valid_count_part1);
     println!("Part 2 valid passwords: {}", valid_count_part2);
 }
-
-#[test]
-fn test_part1_simple_example() {
-    let test_strings = ["1-3 a: abcde", "1-3 b: cdefg", "2-9 c: ccccccccc"];
-    let p0 = PasswordWithPolicy::from_string(test_strings[0]);
-    let p1 = PasswordWithPolicy::from_string(test_strings[1]);
-    let p2 = PasswordWithPolicy::from_string(test_strings[2]);
-    assert_eq!(p0.is_valid_part1(), true);
-    assert_eq!(p1.is_valid_part1(), false);
-    assert_eq!(p2.is_valid_part1(), true);
-}
-
-#[test]
-fn test_part2_simple_example() {
-    let test_strings = ["1-3 a: abcde", "1-3 b: cdefg", "2-9 c: ccccccccc"];
-    let p0 = PasswordWithPolicy::from_string(test_strings[0]);
-    let p1 = PasswordWithPolicy::from_string(test_strings[1]);
-    let p2 = PasswordWithPolicy::from_string(test_strings[2]);
-    assert_eq!(p0.is_valid_part2(), true);
-    assert_eq!(p1.is_valid_part2(), false);
-    assert_eq!(p2.is_valid_part2(), false);
-}
-
-#[test]
-fn test_policy_from_string() {
-    let p = Policy::from_string("1-3 a");
-    assert_eq!(
-        p,
-        Policy {
-            required_char: 'a',
-            required_min: 1,
-            required_max: 3
-        }
-    );
-}
-
-#[test]
-fn test_password_with_policy_from_string() {
-    let password_with_policy = PasswordWithPolicy::from_string("1-3 a: abcde");
-    assert_eq!(
-        password_with_policy,
-        PasswordWithPolicy {
-            policy: Policy {
-                required_char: 'a',
-                required_min: 1,
-                required_max: 3
-            },
-            password: "abcde".to_string()
-        }
-    );
-
-    let password_with_policy = PasswordWithPolicy::from_string("1-3 b: cdefg");
-    assert_eq!(
-        password_with_policy,
-        PasswordWithPolicy {
-            policy: Policy {
-                required_char: 'b',
-                required_min: 1,
-                required_max: 3
-            },
-            password: "cdefg".to_string()
-        }
-    );
-
-    let password_with_policy = PasswordWithPolicy::from_string("2-9 c: ccccccccc");
-    assert_eq!(
-        password_with_policy,
-        PasswordWithPolicy {
-            policy: Policy {
-                required_char: 'c',
-                required_min: 2,
-                required_max: 9
-            },
-            password: "ccccccccc".to_string()
-        }
-    );
-}
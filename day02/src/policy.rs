@@ -0,0 +1,469 @@
+//! Policy grammar:
+//!
+//! ```text
+//! policy  := clause (';' clause)*
+//! clause  := range ' '+ charset
+//! range   := number? '-' number?        at least one of the bounds is required
+//! charset := char (',' char)*  |  '[' (char ('-' char)?)+ ']'
+//! ```
+//!
+//! Whitespace around clauses is ignored. `,`, `;`, `[` and `]` can't be used as plain chars.
+
+use crate::rules::{OccurrenceRange, PolicySet, PositionalXor};
+use crate::unicode::Normalization;
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+/// The chars a clause applies to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CharSet {
+    /// A list of chars, e.g. `a,b`.
+    Chars(Vec<char>),
+    /// A character class of inclusive ranges, e.g. `[0-9x]` is `[('0', '9'), ('x', 'x')]`.
+    Class(Vec<(char, char)>),
+}
+
+impl CharSet {
+    pub fn single(c: char) -> CharSet {
+        CharSet::Chars(vec![c])
+    }
+
+    /// Normalizes the listed chars. Chars that don't normalize to a single char are kept as they are.
+    /// The bounds of character classes are compared by code point and left alone.
+    pub fn normalized(&self, normalization: Normalization) -> CharSet {
+        match self {
+            CharSet::Chars(chars) => CharSet::Chars(
+                chars
+                    .iter()
+                    .map(|&c| normalization.apply_char(c).unwrap_or(c))
+                    .collect(),
+            ),
+            CharSet::Class(_) => self.clone(),
+        }
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        match self {
+            CharSet::Chars(chars) => chars.contains(&c),
+            CharSet::Class(ranges) => ranges.iter().any(|&(from, to)| from <= c && c <= to),
+        }
+    }
+}
+
+impl fmt::Display for CharSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CharSet::Chars(chars) => {
+                for (id, c) in chars.iter().enumerate() {
+                    if id > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", c)?;
+                }
+            }
+            CharSet::Class(ranges) => {
+                write!(f, "[")?;
+                for &(from, to) in ranges {
+                    match from == to {
+                        true => write!(f, "{}", from)?,
+                        false => write!(f, "{}-{}", from, to)?,
+                    }
+                }
+                write!(f, "]")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A range with optional bounds, applied to a set of chars.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clause {
+    pub min: Option<usize>,
+    pub max: Option<usize>,
+    pub chars: CharSet,
+}
+
+impl fmt::Display for Clause {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(min) = self.min {
+            write!(f, "{}", min)?;
+        }
+        write!(f, "-")?;
+        if let Some(max) = self.max {
+            write!(f, "{}", max)?;
+        }
+        write!(f, " {}", self.chars)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Policy {
+    pub clauses: Vec<Clause>,
+}
+
+impl Policy {
+    pub fn normalized(&self, normalization: Normalization) -> Policy {
+        Policy {
+            clauses: self
+                .clauses
+                .iter()
+                .map(|clause| Clause {
+                    chars: clause.chars.normalized(normalization),
+                    ..clause.clone()
+                })
+                .collect(),
+        }
+    }
+
+    /// The chars of each clause have to occur between min and max times.
+    /// A missing min or max doesn't restrict the count.
+    pub fn part1_rule(&self) -> PolicySet {
+        PolicySet::All(
+            self.clauses
+                .iter()
+                .map(|clause| {
+                    PolicySet::rule(OccurrenceRange {
+                        chars: clause.chars.clone(),
+                        min: clause.min.unwrap_or(0),
+                        max: clause.max.unwrap_or(usize::MAX),
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    /// For each clause, a char of its set has to be at exactly one of the 1-based positions min and max.
+    /// A missing min or max is a position that never matches.
+    pub fn part2_rule(&self) -> PolicySet {
+        PolicySet::All(
+            self.clauses
+                .iter()
+                .map(|clause| {
+                    PolicySet::rule(PositionalXor {
+                        chars: clause.chars.clone(),
+                        positions: (clause.min.unwrap_or(0), clause.max.unwrap_or(0)),
+                    })
+                })
+                .collect(),
+        )
+    }
+}
+
+impl fmt::Display for Policy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (id, clause) in self.clauses.iter().enumerate() {
+            if id > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", clause)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyParseError {
+    /// Byte range in the parsed string.
+    pub span: Range<usize>,
+    pub message: String,
+}
+
+impl PolicyParseError {
+    /// Renders the error below the source, with the span marked by carets.
+    pub fn annotate(&self, source: &str) -> String {
+        let start = source[..self.span.start].chars().count();
+        let length = source[self.span.clone()].chars().count().max(1);
+        format!(
+            "{}\n{}{} {}",
+            source,
+            " ".repeat(start),
+            "^".repeat(length),
+            self.message
+        )
+    }
+}
+
+impl fmt::Display for PolicyParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} at {}..{}",
+            self.message, self.span.start, self.span.end
+        )
+    }
+}
+
+impl std::error::Error for PolicyParseError {}
+
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn error<T>(&self, span: Range<usize>, message: &str) -> Result<T, PolicyParseError> {
+        Err(PolicyParseError {
+            span,
+            message: message.to_string(),
+        })
+    }
+
+    /// Error for the char at the current position, or for the end of the input.
+    fn unexpected<T>(&self, expected: &str) -> Result<T, PolicyParseError> {
+        match self.peek() {
+            Some(c) => self.error(
+                self.position..self.position + c.len_utf8(),
+                &format!("expected {}, found {:?}", expected, c),
+            ),
+            None => self.error(
+                self.position..self.position,
+                &format!("expected {}, found end of input", expected),
+            ),
+        }
+    }
+
+    fn skip_whitespace(&mut self) -> usize {
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_whitespace()) {
+            self.bump();
+        }
+        self.position - start
+    }
+
+    fn number(&mut self) -> Result<Option<usize>, PolicyParseError> {
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.bump();
+        }
+        if start == self.position {
+            return Ok(None);
+        }
+        match self.input[start..self.position].parse() {
+            Ok(number) => Ok(Some(number)),
+            Err(_) => self.error(start..self.position, "number too large"),
+        }
+    }
+
+    fn plain_char(&mut self) -> Result<char, PolicyParseError> {
+        match self.peek() {
+            Some(c) if !c.is_whitespace() && !",;[]".contains(c) => {
+                self.bump();
+                Ok(c)
+            }
+            _ => self.unexpected("a char"),
+        }
+    }
+
+    fn clause(&mut self) -> Result<Clause, PolicyParseError> {
+        self.skip_whitespace();
+        let range_start = self.position;
+        let min = self.number()?;
+        if self.peek() != Some('-') {
+            return self.unexpected("'-'");
+        }
+        self.bump();
+        let max = self.number()?;
+        if min.is_none() && max.is_none() {
+            return self.error(range_start..self.position, "range without bounds");
+        }
+        if let (Some(min), Some(max)) = (min, max) {
+            if min > max {
+                return self.error(range_start..self.position, "range minimum exceeds maximum");
+            }
+        }
+        if self.skip_whitespace() == 0 {
+            return self.unexpected("whitespace");
+        }
+
+        let chars = match self.peek() {
+            Some('[') => self.class()?,
+            _ => {
+                let mut chars = vec![self.plain_char()?];
+                while self.peek() == Some(',') {
+                    self.bump();
+                    chars.push(self.plain_char()?);
+                }
+                CharSet::Chars(chars)
+            }
+        };
+        self.skip_whitespace();
+        Ok(Clause { min, max, chars })
+    }
+
+    fn class(&mut self) -> Result<CharSet, PolicyParseError> {
+        let class_start = self.position;
+        self.bump(); // '['
+        let mut ranges = Vec::new();
+        while self.peek() != Some(']') {
+            let from_start = self.position;
+            let from = self.plain_char()?;
+            let to = match self.peek() {
+                Some('-') => {
+                    self.bump();
+                    self.plain_char()?
+                }
+                _ => from,
+            };
+            if from > to {
+                return self.error(from_start..self.position, "inverted character range");
+            }
+            ranges.push((from, to));
+        }
+        self.bump(); // ']'
+        if ranges.is_empty() {
+            return self.error(class_start..self.position, "empty character class");
+        }
+        Ok(CharSet::Class(ranges))
+    }
+
+    fn policy(&mut self) -> Result<Policy, PolicyParseError> {
+        let mut clauses = vec![self.clause()?];
+        while self.peek() == Some(';') {
+            self.bump();
+            clauses.push(self.clause()?);
+        }
+        if self.peek().is_some() {
+            return self.unexpected("';' or end of input");
+        }
+        Ok(Policy { clauses })
+    }
+}
+
+impl FromStr for Policy {
+    type Err = PolicyParseError;
+
+    fn from_str(policy_str: &str) -> Result<Self, Self::Err> {
+        Parser {
+            input: policy_str,
+            position: 0,
+        }
+        .policy()
+    }
+}
+
+#[cfg(test)]
+fn parse_error(policy_str: &str) -> (Range<usize>, String) {
+    let error = policy_str.parse::<Policy>().unwrap_err();
+    (error.span, error.message)
+}
+
+#[test]
+fn test_parse_extended_grammar() {
+    assert_eq!(
+        "1-3 a,b".parse(),
+        Ok(Policy {
+            clauses: vec![Clause {
+                min: Some(1),
+                max: Some(3),
+                chars: CharSet::Chars(vec!['a', 'b']),
+            }]
+        })
+    );
+    assert_eq!(
+        " 2-4 [0-9x];3- x ; -5 ü ".parse(),
+        Ok(Policy {
+            clauses: vec![
+                Clause {
+                    min: Some(2),
+                    max: Some(4),
+                    chars: CharSet::Class(vec![('0', '9'), ('x', 'x')]),
+                },
+                Clause {
+                    min: Some(3),
+                    max: None,
+                    chars: CharSet::single('x'),
+                },
+                Clause {
+                    min: None,
+                    max: Some(5),
+                    chars: CharSet::single('ü'),
+                },
+            ]
+        })
+    );
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(
+        parse_error(""),
+        (0..0, "expected '-', found end of input".to_string())
+    );
+    assert_eq!(
+        parse_error("1-3a"),
+        (3..4, "expected whitespace, found 'a'".to_string())
+    );
+    assert_eq!(
+        parse_error("- a"),
+        (0..1, "range without bounds".to_string())
+    );
+    assert_eq!(
+        parse_error("5-3 a"),
+        (0..3, "range minimum exceeds maximum".to_string())
+    );
+    assert_eq!(
+        parse_error("1-3 a,"),
+        (6..6, "expected a char, found end of input".to_string())
+    );
+    assert_eq!(
+        parse_error("1-3 [9-0]"),
+        (5..8, "inverted character range".to_string())
+    );
+    assert_eq!(
+        parse_error("1-3 []"),
+        (4..6, "empty character class".to_string())
+    );
+    assert_eq!(
+        parse_error("1-3 [a"),
+        (6..6, "expected a char, found end of input".to_string())
+    );
+    assert_eq!(
+        parse_error("1-3 a b"),
+        (6..7, "expected ';' or end of input, found 'b'".to_string())
+    );
+    assert_eq!(
+        parse_error("99999999999999999999999-1 a"),
+        (0..23, "number too large".to_string())
+    );
+}
+
+#[test]
+fn test_error_annotation_and_display() {
+    let error = "1-3 a;x-2 b".parse::<Policy>().unwrap_err();
+    assert_eq!(error.to_string(), "expected '-', found 'x' at 6..7");
+    assert_eq!(
+        error.annotate("1-3 a;x-2 b"),
+        "1-3 a;x-2 b\n      ^ expected '-', found 'x'"
+    );
+
+    let policy: Policy = "2-4 [0-9x];3- x;-5 a,b".parse().unwrap();
+    assert_eq!(policy.to_string(), "2-4 [0-9x]; 3- x; -5 a,b");
+    assert_eq!(policy.to_string().parse(), Ok(policy));
+}
+
+#[test]
+fn test_evaluate_extended_policies() {
+    use crate::rules::PasswordRule;
+
+    let policy: Policy = "1-2 a,b; 1- [0-9]".parse().unwrap();
+    assert!(policy.part1_rule().is_satisfied("ab1"));
+    assert!(!policy.part1_rule().is_satisfied("aba1"));
+    assert!(!policy.part1_rule().is_satisfied("ab"));
+
+    let policy: Policy = "1-3 a,b; 2- [0-9]".parse().unwrap();
+    assert!(policy.part2_rule().is_satisfied("b1c"));
+    assert!(!policy.part2_rule().is_satisfied("ba1"));
+    assert!(!policy.part2_rule().is_satisfied("bcd"));
+}
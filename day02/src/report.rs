@@ -0,0 +1,316 @@
+use crate::rules::PasswordRule;
+use crate::unicode::ValidationOptions;
+use crate::PasswordWithPolicy;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// An additional rule that is checked for every entry next to its policy, e.g. a breach check.
+pub type NamedRule = (String, Arc<dyn PasswordRule + Send + Sync>);
+
+/// Outcome of checking one rule for one entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleResult {
+    pub rule: String,
+    /// Why the rule failed, `None` if it passed.
+    pub failure: Option<String>,
+}
+
+/// Outcome of validating one line of a password database.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationReport {
+    /// 1-based line number.
+    pub line: usize,
+    pub entry: String,
+    pub results: Vec<RuleResult>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReportFormat {
+    Text,
+    JsonLines,
+    Csv,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "text" => Ok(ReportFormat::Text),
+            "json" | "jsonl" => Ok(ReportFormat::JsonLines),
+            "csv" => Ok(ReportFormat::Csv),
+            _ => Err(format!("unknown report format {:?}", format)),
+        }
+    }
+}
+
+impl ValidationReport {
+    /// Parses the entry and checks the part 1 and part 2 rules of its policy.
+    /// An unparsable entry gets a single failed "parse" result.
+    pub fn for_entry(line: usize, entry: &str, options: &ValidationOptions) -> ValidationReport {
+        ValidationReport::with_rules(line, entry, options, &[])
+    }
+
+    /// Like `for_entry`, with the results of the additional rules after those of the policy.
+    pub fn with_rules(
+        line: usize,
+        entry: &str,
+        options: &ValidationOptions,
+        rules: &[NamedRule],
+    ) -> ValidationReport {
+        let results = match entry.parse::<PasswordWithPolicy>() {
+            Ok(password_with_policy) => vec![
+                RuleResult {
+                    rule: "part1".to_string(),
+                    failure: password_with_policy.check_part1_with(options).err(),
+                },
+                RuleResult {
+                    rule: "part2".to_string(),
+                    failure: password_with_policy.check_part2_with(options).err(),
+                },
+            ]
+            .into_iter()
+            .chain(rules.iter().map(|(name, rule)| {
+                RuleResult {
+                    rule: name.clone(),
+                    failure: password_with_policy
+                        .check_with(rule.as_ref(), options)
+                        .err(),
+                }
+            }))
+            .collect(),
+            Err(e) => vec![RuleResult {
+                rule: "parse".to_string(),
+                failure: Some(e.to_string()),
+            }],
+        };
+        ValidationReport {
+            line,
+            entry: entry.to_string(),
+            results,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.results.iter().all(|result| result.failure.is_none())
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "line {}: {:?}: {}",
+            self.line,
+            self.entry,
+            if self.is_valid() { "valid" } else { "invalid" }
+        );
+        for result in &self.results {
+            match &result.failure {
+                None => write!(text, "\n  {}: ok", result.rule).unwrap(),
+                Some(reason) => write!(text, "\n  {}: {}", result.rule, reason).unwrap(),
+            }
+        }
+        text
+    }
+
+    pub fn to_json(&self) -> String {
+        let results: Vec<String> = self
+            .results
+            .iter()
+            .map(|result| {
+                format!(
+                    "{{\"rule\":{},\"valid\":{},\"reason\":{}}}",
+                    json_string(&result.rule),
+                    result.failure.is_none(),
+                    result
+                        .failure
+                        .as_ref()
+                        .map_or("null".to_string(), |reason| json_string(reason))
+                )
+            })
+            .collect();
+        format!(
+            "{{\"line\":{},\"entry\":{},\"valid\":{},\"results\":[{}]}}",
+            self.line,
+            json_string(&self.entry),
+            self.is_valid(),
+            results.join(",")
+        )
+    }
+
+    /// One row per rule, see `CSV_HEADER`.
+    pub fn to_csv_rows(&self) -> Vec<String> {
+        self.results
+            .iter()
+            .map(|result| {
+                format!(
+                    "{},{},{},{},{}",
+                    self.line,
+                    csv_field(&self.entry),
+                    csv_field(&result.rule),
+                    result.failure.is_none(),
+                    csv_field(result.failure.as_deref().unwrap_or(""))
+                )
+            })
+            .collect()
+    }
+}
+
+pub const CSV_HEADER: &str = "line,entry,rule,valid,reason";
+
+/// Writes the reports, in CSV preceded by the header line.
+pub fn write_reports<'a, W: Write>(
+    mut writer: W,
+    reports: impl IntoIterator<Item = &'a ValidationReport>,
+    format: ReportFormat,
+) -> io::Result<()> {
+    if format == ReportFormat::Csv {
+        writeln!(writer, "{}", CSV_HEADER)?;
+    }
+    for report in reports {
+        write_report(&mut writer, report, format)?;
+    }
+    Ok(())
+}
+
+/// Writes a single report. CSV is written without header.
+pub fn write_report<W: Write>(
+    mut writer: W,
+    report: &ValidationReport,
+    format: ReportFormat,
+) -> io::Result<()> {
+    match format {
+        ReportFormat::Text => writeln!(writer, "{}", report.to_text()),
+        ReportFormat::JsonLines => writeln!(writer, "{}", report.to_json()),
+        ReportFormat::Csv => {
+            for row in report.to_csv_rows() {
+                writeln!(writer, "{}", row)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[test]
+fn test_report_reasons() {
+    let options = ValidationOptions::default();
+    let report = ValidationReport::for_entry(1, "1-3 a: abcde", &options);
+    assert!(report.is_valid());
+
+    let report = ValidationReport::for_entry(2, "1-3 a: aaaaa", &options);
+    assert!(!report.is_valid());
+    assert_eq!(
+        report.results,
+        [
+            RuleResult {
+                rule: "part1".to_string(),
+                failure: Some("char 'a' occurs 5 times, allowed 1-3".to_string()),
+            },
+            RuleResult {
+                rule: "part2".to_string(),
+                failure: Some("'a' at both of positions 1 and 3".to_string()),
+            },
+        ]
+    );
+
+    let report = ValidationReport::for_entry(3, "1-9 b: abbbb", &options);
+    assert_eq!(
+        report.results[1].failure.as_deref(),
+        Some("position 9 out of range for length 5")
+    );
+
+    let report = ValidationReport::for_entry(4, "1-3 a abcde", &options);
+    assert_eq!(report.results[0].rule, "parse");
+    assert!(!report.is_valid());
+}
+
+#[test]
+fn test_report_formats() {
+    let options = ValidationOptions::default();
+    let reports = vec![
+        ValidationReport::for_entry(1, "1-3 a: abcde", &options),
+        ValidationReport::for_entry(2, "1-3 b: cdefg", &options),
+    ];
+
+    let mut text = Vec::new();
+    write_reports(&mut text, &reports, ReportFormat::Text).unwrap();
+    assert_eq!(
+        String::from_utf8(text).unwrap(),
+        "line 1: \"1-3 a: abcde\": valid\n  part1: ok\n  part2: ok\n\
+         line 2: \"1-3 b: cdefg\": invalid\n  part1: char 'b' occurs 0 times, allowed 1-3\n  part2: 'b' at none of positions 1 and 3\n"
+    );
+
+    let mut json = Vec::new();
+    write_reports(&mut json, &reports[1..], ReportFormat::JsonLines).unwrap();
+    assert_eq!(
+        String::from_utf8(json).unwrap(),
+        "{\"line\":2,\"entry\":\"1-3 b: cdefg\",\"valid\":false,\"results\":[\
+         {\"rule\":\"part1\",\"valid\":false,\"reason\":\"char 'b' occurs 0 times, allowed 1-3\"},\
+         {\"rule\":\"part2\",\"valid\":false,\"reason\":\"'b' at none of positions 1 and 3\"}]}\n"
+    );
+
+    let mut csv = Vec::new();
+    write_reports(&mut csv, &reports[..1], ReportFormat::Csv).unwrap();
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        "line,entry,rule,valid,reason\n1,1-3 a: abcde,part1,true,\n1,1-3 a: abcde,part2,true,\n"
+    );
+}
+
+#[test]
+fn test_escaping() {
+    assert_eq!(json_string("a\"b\\c\n\u{1}"), "\"a\\\"b\\\\c\\n\\u0001\"");
+    assert_eq!(csv_field("a,b"), "\"a,b\"");
+    assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    assert_eq!(csv_field("plain"), "plain");
+    assert_eq!("jsonl".parse(), Ok(ReportFormat::JsonLines));
+    assert!("xml".parse::<ReportFormat>().is_err());
+}
+
+#[test]
+fn test_report_with_additional_rules() {
+    use crate::rules::ForbiddenChars;
+
+    let rules: Vec<NamedRule> = vec![(
+        "no-digits".to_string(),
+        Arc::new(ForbiddenChars {
+            chars: ('0'..='9').collect(),
+        }),
+    )];
+    let options = ValidationOptions::default();
+    let report = ValidationReport::with_rules(1, "1-3 a: abc1", &options, &rules);
+    assert_eq!(report.results.len(), 3);
+    assert_eq!(report.results[2].rule, "no-digits");
+    assert_eq!(
+        report.results[2].failure.as_deref(),
+        Some("forbidden char '1' at position 4")
+    );
+    let report = ValidationReport::with_rules(2, "1-3 a abc1", &options, &rules);
+    assert_eq!(report.results.len(), 1);
+}
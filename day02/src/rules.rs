@@ -0,0 +1,585 @@
+use crate::policy::CharSet;
+use crate::unicode::{graphemes, single_char};
+use regex::Regex;
+use std::collections::HashMap;
+
+/// A single requirement that a password has to fulfill.
+///
+/// Rules count and index user-perceived characters (grapheme clusters), not `char`s.
+/// A grapheme only matches a char if it consists of exactly that char,
+/// so normalize the password first (see `unicode::Normalization`) to compose as much as possible.
+pub trait PasswordRule {
+    /// Short description of the requirement, e.g. "1-3 times 'a'".
+    fn describe(&self) -> String;
+
+    /// Returns the reason why the password doesn't satisfy the rule.
+    fn check(&self, password: &str) -> Result<(), String>;
+
+    /// Like `check`, but also gets the password as it was entered, before normalization.
+    /// Rules that depend on the exact bytes, like hashes, override this.
+    fn check_entered(&self, entered: &str, normalized: &str) -> Result<(), String> {
+        let _ = entered;
+        self.check(normalized)
+    }
+
+    fn is_satisfied(&self, password: &str) -> bool {
+        self.check(password).is_ok()
+    }
+}
+
+/// "'a'" for a single char, "one of a,b" or "one of [0-9]" for larger sets.
+fn describe_set(chars: &CharSet) -> String {
+    match chars {
+        CharSet::Chars(chars) if chars.len() == 1 => format!("{:?}", chars[0]),
+        chars => format!("one of {}", chars),
+    }
+}
+
+fn describe_range(min: usize, max: usize) -> String {
+    match max {
+        usize::MAX => format!("at least {}", min),
+        _ => format!("{}-{}", min, max),
+    }
+}
+
+/// The chars of the set have to occur at least `min` and at most `max` times in total.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OccurrenceRange {
+    pub chars: CharSet,
+    pub min: usize,
+    pub max: usize,
+}
+
+impl PasswordRule for OccurrenceRange {
+    fn describe(&self) -> String {
+        format!(
+            "{} times {}",
+            describe_range(self.min, self.max),
+            describe_set(&self.chars)
+        )
+    }
+
+    fn check(&self, password: &str) -> Result<(), String> {
+        let occurrence_count = graphemes(password)
+            .filter(|grapheme| is_in_set(grapheme, &self.chars))
+            .count();
+        if self.min <= occurrence_count && occurrence_count <= self.max {
+            return Ok(());
+        }
+        let subject = match &self.chars {
+            CharSet::Chars(chars) if chars.len() == 1 => format!("char {:?} occurs", chars[0]),
+            chars => format!("chars {} occur", chars),
+        };
+        Err(format!(
+            "{} {} times, allowed {}",
+            subject,
+            occurrence_count,
+            describe_range(self.min, self.max)
+        ))
+    }
+}
+
+fn is_in_set(grapheme: &str, chars: &CharSet) -> bool {
+    single_char(grapheme).is_some_and(|c| chars.contains(c))
+}
+
+/// Returns whether a char of the set is at the 1-based position.
+/// Position 0 and positions past the end never match.
+fn is_char_at(password: &str, chars: &CharSet, position: usize) -> bool {
+    position > 0
+        && graphemes(password)
+            .nth(position - 1)
+            .is_some_and(|grapheme| is_in_set(grapheme, chars))
+}
+
+/// The reason for a failed positional check, naming out of range positions first.
+fn positional_failure(
+    password: &str,
+    chars: &CharSet,
+    positions: (usize, usize),
+    matches: &str,
+) -> String {
+    let length = graphemes(password).count();
+    for &position in &[positions.0, positions.1] {
+        if position == 0 || position > length {
+            return format!("position {} out of range for length {}", position, length);
+        }
+    }
+    format!(
+        "{} at {} of positions {} and {}",
+        describe_set(chars),
+        matches,
+        positions.0,
+        positions.1
+    )
+}
+
+/// A char of the set has to be at exactly one of the two 1-based positions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionalXor {
+    pub chars: CharSet,
+    pub positions: (usize, usize),
+}
+
+impl PasswordRule for PositionalXor {
+    fn describe(&self) -> String {
+        format!(
+            "{} at exactly one of positions {} and {}",
+            describe_set(&self.chars),
+            self.positions.0,
+            self.positions.1
+        )
+    }
+
+    fn check(&self, password: &str) -> Result<(), String> {
+        let at_first = is_char_at(password, &self.chars, self.positions.0);
+        let at_second = is_char_at(password, &self.chars, self.positions.1);
+        match (at_first, at_second) {
+            (true, false) | (false, true) => Ok(()),
+            (true, true) => Err(positional_failure(
+                password,
+                &self.chars,
+                self.positions,
+                "both",
+            )),
+            (false, false) => Err(positional_failure(
+                password,
+                &self.chars,
+                self.positions,
+                "none",
+            )),
+        }
+    }
+}
+
+/// A char of the set has to be at both of the two 1-based positions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionalAnd {
+    pub chars: CharSet,
+    pub positions: (usize, usize),
+}
+
+impl PasswordRule for PositionalAnd {
+    fn describe(&self) -> String {
+        format!(
+            "{} at both positions {} and {}",
+            describe_set(&self.chars),
+            self.positions.0,
+            self.positions.1
+        )
+    }
+
+    fn check(&self, password: &str) -> Result<(), String> {
+        let at_first = is_char_at(password, &self.chars, self.positions.0);
+        let at_second = is_char_at(password, &self.chars, self.positions.1);
+        match (at_first, at_second) {
+            (true, true) => Ok(()),
+            (false, false) => Err(positional_failure(
+                password,
+                &self.chars,
+                self.positions,
+                "none",
+            )),
+            _ => Err(positional_failure(
+                password,
+                &self.chars,
+                self.positions,
+                "only one",
+            )),
+        }
+    }
+}
+
+/// None of the chars may occur in the password.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForbiddenChars {
+    pub chars: Vec<char>,
+}
+
+impl PasswordRule for ForbiddenChars {
+    fn describe(&self) -> String {
+        format!("none of {:?}", self.chars)
+    }
+
+    /// Graphemes are forbidden if any of their chars is, e.g. a forbidden combining mark.
+    fn check(&self, password: &str) -> Result<(), String> {
+        for (id, grapheme) in graphemes(password).enumerate() {
+            if let Some(c) = grapheme.chars().find(|c| self.chars.contains(c)) {
+                return Err(match single_char(grapheme) {
+                    Some(_) => format!("forbidden char {:?} at position {}", c, id + 1),
+                    None => format!(
+                        "forbidden char {:?} in {:?} at position {}",
+                        c,
+                        grapheme,
+                        id + 1
+                    ),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The password's Shannon entropy, summed over all its graphemes, has to be at least `min_bits`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MinEntropy {
+    pub min_bits: f64,
+}
+
+impl MinEntropy {
+    pub fn entropy_bits(password: &str) -> f64 {
+        let mut frequencies = HashMap::<&str, usize>::new();
+        let mut length = 0;
+        for grapheme in graphemes(password) {
+            *frequencies.entry(grapheme).or_default() += 1;
+            length += 1;
+        }
+        let bits_per_char: f64 = frequencies
+            .values()
+            .map(|&count| {
+                let p = count as f64 / length as f64;
+                -p * p.log2()
+            })
+            .sum();
+        bits_per_char * length as f64
+    }
+}
+
+impl PasswordRule for MinEntropy {
+    fn describe(&self) -> String {
+        format!("at least {:.1} bits of entropy", self.min_bits)
+    }
+
+    fn check(&self, password: &str) -> Result<(), String> {
+        let bits = MinEntropy::entropy_bits(password);
+        match bits >= self.min_bits {
+            true => Ok(()),
+            false => Err(format!(
+                "entropy {:.1} bits, required at least {:.1}",
+                bits, self.min_bits
+            )),
+        }
+    }
+}
+
+/// The password has to match the regex.
+#[derive(Debug, Clone)]
+pub struct RegexMatch {
+    pub regex: Regex,
+}
+
+impl RegexMatch {
+    pub fn new(pattern: &str) -> Result<RegexMatch, regex::Error> {
+        Ok(RegexMatch {
+            regex: Regex::new(pattern)?,
+        })
+    }
+}
+
+impl PasswordRule for RegexMatch {
+    fn describe(&self) -> String {
+        format!("matches /{}/", self.regex)
+    }
+
+    fn check(&self, password: &str) -> Result<(), String> {
+        match self.regex.is_match(password) {
+            true => Ok(()),
+            false => Err(format!("doesn't match /{}/", self.regex)),
+        }
+    }
+}
+
+/// Combination of rules.
+pub enum PolicySet {
+    Rule(Box<dyn PasswordRule>),
+    /// Satisfied if all of the sets are. An empty `All` is always satisfied.
+    All(Vec<PolicySet>),
+    /// Satisfied if any of the sets is. An empty `Any` is never satisfied.
+    Any(Vec<PolicySet>),
+    Not(Box<PolicySet>),
+}
+
+impl PolicySet {
+    pub fn rule<R: PasswordRule + 'static>(rule: R) -> PolicySet {
+        PolicySet::Rule(Box::new(rule))
+    }
+
+    pub fn and(self, other: PolicySet) -> PolicySet {
+        match self {
+            PolicySet::All(mut sets) => {
+                sets.push(other);
+                PolicySet::All(sets)
+            }
+            set => PolicySet::All(vec![set, other]),
+        }
+    }
+
+    pub fn or(self, other: PolicySet) -> PolicySet {
+        match self {
+            PolicySet::Any(mut sets) => {
+                sets.push(other);
+                PolicySet::Any(sets)
+            }
+            set => PolicySet::Any(vec![set, other]),
+        }
+    }
+
+    pub fn negate(self) -> PolicySet {
+        PolicySet::Not(Box::new(self))
+    }
+}
+
+impl PasswordRule for PolicySet {
+    fn describe(&self) -> String {
+        let describe_all = |sets: &[PolicySet], separator| {
+            sets.iter()
+                .map(|set| set.describe())
+                .collect::<Vec<String>>()
+                .join(separator)
+        };
+        match self {
+            PolicySet::Rule(rule) => rule.describe(),
+            PolicySet::All(sets) if sets.len() == 1 => sets[0].describe(),
+            PolicySet::All(sets) => format!("({})", describe_all(sets, " and ")),
+            PolicySet::Any(sets) => format!("({})", describe_all(sets, " or ")),
+            PolicySet::Not(set) => format!("not {}", set.describe()),
+        }
+    }
+
+    fn check(&self, password: &str) -> Result<(), String> {
+        self.check_entered(password, password)
+    }
+
+    /// Failures of `All` list the reasons of every failed set, `Any` those of all sets.
+    fn check_entered(&self, entered: &str, normalized: &str) -> Result<(), String> {
+        match self {
+            PolicySet::Rule(rule) => rule.check_entered(entered, normalized),
+            PolicySet::All(sets) => {
+                let reasons: Vec<String> = sets
+                    .iter()
+                    .filter_map(|set| set.check_entered(entered, normalized).err())
+                    .collect();
+                match reasons.is_empty() {
+                    true => Ok(()),
+                    false => Err(reasons.join("; ")),
+                }
+            }
+            PolicySet::Any(sets) => {
+                let mut reasons = Vec::new();
+                for set in sets {
+                    match set.check_entered(entered, normalized) {
+                        Ok(()) => return Ok(()),
+                        Err(reason) => reasons.push(reason),
+                    }
+                }
+                Err(format!("none satisfied: {}", reasons.join(" | ")))
+            }
+            PolicySet::Not(set) => match set.check_entered(entered, normalized) {
+                Ok(()) => Err(format!("must not satisfy: {}", set.describe())),
+                Err(_) => Ok(()),
+            },
+        }
+    }
+}
+
+#[test]
+fn test_occurrence_range() {
+    let rule = OccurrenceRange {
+        chars: CharSet::single('a'),
+        min: 1,
+        max: 3,
+    };
+    assert!(rule.is_satisfied("abcde"));
+    assert!(rule.is_satisfied("aaa"));
+    assert!(!rule.is_satisfied("aaaa"));
+    assert!(!rule.is_satisfied("bcd"));
+
+    let rule = OccurrenceRange {
+        chars: CharSet::Class(vec![('0', '9')]),
+        min: 2,
+        max: usize::MAX,
+    };
+    assert!(rule.is_satisfied("a1b2"));
+    assert!(!rule.is_satisfied("ab2"));
+}
+
+#[test]
+fn test_positional_rules() {
+    let xor = PositionalXor {
+        chars: CharSet::single('a'),
+        positions: (1, 3),
+    };
+    let and = PositionalAnd {
+        chars: CharSet::single('a'),
+        positions: (1, 3),
+    };
+    assert!(xor.is_satisfied("abcde"));
+    assert!(!and.is_satisfied("abcde"));
+    assert!(!xor.is_satisfied("abade"));
+    assert!(and.is_satisfied("abade"));
+    assert!(!xor.is_satisfied("bbbbb"));
+    // out of range positions don't match, instead of panicking
+    assert!(xor.is_satisfied("a"));
+    assert!(!and.is_satisfied("a"));
+    assert!(!xor.is_satisfied(""));
+    let xor = PositionalXor {
+        chars: CharSet::single('a'),
+        positions: (0, 3),
+    };
+    assert!(xor.is_satisfied("aaa"));
+    assert!(!xor.is_satisfied("aab"));
+}
+
+#[test]
+fn test_rules_use_graphemes() {
+    let decomposed_e_acute = "e\u{301}";
+    let rule = OccurrenceRange {
+        chars: CharSet::single('e'),
+        min: 1,
+        max: 1,
+    };
+    assert!(!rule.is_satisfied(&format!("{}x", decomposed_e_acute)));
+    assert!(rule.is_satisfied(&format!("{}e", decomposed_e_acute)));
+
+    let xor = PositionalXor {
+        chars: CharSet::single('x'),
+        positions: (2, 9),
+    };
+    assert!(xor.is_satisfied(&format!("{}x", decomposed_e_acute)));
+    assert!(xor.is_satisfied("\u{1f468}\u{200d}\u{1f469}x"));
+    assert_eq!(
+        MinEntropy::entropy_bits(&format!("{}e", decomposed_e_acute)),
+        2.0
+    );
+
+    let forbidden = ForbiddenChars {
+        chars: vec!['\u{301}'],
+    };
+    assert_eq!(
+        forbidden.check(&format!(
+            "\u{1f468}\u{200d}\u{1f469}{}x",
+            decomposed_e_acute
+        )),
+        Err(r#"forbidden char '\u{301}' in "e\u{301}" at position 2"#.to_string())
+    );
+}
+
+#[test]
+fn test_forbidden_chars_entropy_and_regex() {
+    let forbidden = ForbiddenChars {
+        chars: vec![' ', ':'],
+    };
+    assert!(forbidden.is_satisfied("abc"));
+    assert!(!forbidden.is_satisfied("a:c"));
+
+    assert_eq!(MinEntropy::entropy_bits(""), 0.0);
+    assert_eq!(MinEntropy::entropy_bits("aaaa"), 0.0);
+    assert_eq!(MinEntropy::entropy_bits("abab"), 4.0);
+    assert_eq!(MinEntropy::entropy_bits("abcd"), 8.0);
+    assert!(MinEntropy { min_bits: 8.0 }.is_satisfied("abcd"));
+    assert!(!MinEntropy { min_bits: 8.0 }.is_satisfied("abab"));
+
+    let regex = RegexMatch::new("^[a-z]+[0-9]$").unwrap();
+    assert!(regex.is_satisfied("abc1"));
+    assert!(!regex.is_satisfied("abc"));
+    assert!(RegexMatch::new("(").is_err());
+}
+
+#[test]
+fn test_policy_set() {
+    let has_digit = || PolicySet::rule(RegexMatch::new("[0-9]").unwrap());
+    let has_upper = || PolicySet::rule(RegexMatch::new("[A-Z]").unwrap());
+    let no_spaces = || PolicySet::rule(ForbiddenChars { chars: vec![' '] });
+
+    let policy = has_digit().or(has_upper()).and(no_spaces());
+    assert!(policy.is_satisfied("abc1"));
+    assert!(policy.is_satisfied("Abc"));
+    assert!(!policy.is_satisfied("abc"));
+    assert!(!policy.is_satisfied("Ab c"));
+
+    let policy = has_digit().negate();
+    assert!(policy.is_satisfied("abc"));
+    assert!(!policy.is_satisfied("abc1"));
+
+    assert!(PolicySet::All(vec![]).is_satisfied("x"));
+    assert!(!PolicySet::Any(vec![]).is_satisfied("x"));
+}
+
+#[test]
+fn test_failure_reasons() {
+    let rule = OccurrenceRange {
+        chars: CharSet::single('a'),
+        min: 1,
+        max: 3,
+    };
+    assert_eq!(rule.describe(), "1-3 times 'a'");
+    assert_eq!(
+        rule.check("aaaaa"),
+        Err("char 'a' occurs 5 times, allowed 1-3".to_string())
+    );
+    let rule = OccurrenceRange {
+        chars: CharSet::Class(vec![('0', '9')]),
+        min: 2,
+        max: usize::MAX,
+    };
+    assert_eq!(
+        rule.check("a1"),
+        Err("chars [0-9] occur 1 times, allowed at least 2".to_string())
+    );
+
+    let xor = PositionalXor {
+        chars: CharSet::single('a'),
+        positions: (1, 9),
+    };
+    assert_eq!(xor.describe(), "'a' at exactly one of positions 1 and 9");
+    assert_eq!(
+        xor.check("bbbbb"),
+        Err("position 9 out of range for length 5".to_string())
+    );
+    let xor = PositionalXor {
+        chars: CharSet::Chars(vec!['a', 'b']),
+        positions: (1, 2),
+    };
+    assert_eq!(
+        xor.check("ab"),
+        Err("one of a,b at both of positions 1 and 2".to_string())
+    );
+    let and = PositionalAnd {
+        chars: CharSet::single('a'),
+        positions: (1, 2),
+    };
+    assert_eq!(
+        and.check("ab"),
+        Err("'a' at only one of positions 1 and 2".to_string())
+    );
+
+    assert_eq!(
+        ForbiddenChars { chars: vec![':'] }.check("ab:"),
+        Err("forbidden char ':' at position 3".to_string())
+    );
+    assert_eq!(
+        MinEntropy { min_bits: 8.0 }.check("abab"),
+        Err("entropy 4.0 bits, required at least 8.0".to_string())
+    );
+    assert_eq!(
+        RegexMatch::new("^a").unwrap().check("ba"),
+        Err("doesn't match /^a/".to_string())
+    );
+
+    let policy = PolicySet::rule(RegexMatch::new("^a").unwrap())
+        .and(PolicySet::rule(MinEntropy { min_bits: 8.0 }).negate());
+    assert_eq!(
+        policy.describe(),
+        "(matches /^a/ and not at least 8.0 bits of entropy)"
+    );
+    assert_eq!(
+        policy.check("bcde"),
+        Err("doesn't match /^a/; must not satisfy: at least 8.0 bits of entropy".to_string())
+    );
+    let policy = PolicySet::rule(RegexMatch::new("^a").unwrap())
+        .or(PolicySet::rule(RegexMatch::new("^b").unwrap()));
+    assert_eq!(
+        policy.check("c"),
+        Err("none satisfied: doesn't match /^a/ | doesn't match /^b/".to_string())
+    );
+}
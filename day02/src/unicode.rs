@@ -0,0 +1,73 @@
+use std::borrow::Cow;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Unicode normalization form applied to passwords and policy chars before checking.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Normalization {
+    None,
+    #[default]
+    Nfc,
+    Nfkc,
+}
+
+impl Normalization {
+    pub fn apply<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        match self {
+            Normalization::None => Cow::Borrowed(s),
+            Normalization::Nfc => Cow::Owned(s.nfc().collect()),
+            Normalization::Nfkc => Cow::Owned(s.nfkc().collect()),
+        }
+    }
+
+    /// Normalizes a single char. Returns `None` if it doesn't stay a single char, e.g. 'ﬁ' in NFKC.
+    pub fn apply_char(&self, c: char) -> Option<char> {
+        let mut buffer = [0; 4];
+        let normalized = self.apply(c.encode_utf8(&mut buffer));
+        let mut chars = normalized.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ValidationOptions {
+    pub normalization: Normalization,
+}
+
+/// The user-perceived characters (extended grapheme clusters) of the string.
+pub fn graphemes(s: &str) -> impl Iterator<Item = &str> {
+    s.graphemes(true)
+}
+
+/// Returns the grapheme's char, if the grapheme consists of exactly one char.
+pub fn single_char(grapheme: &str) -> Option<char> {
+    let mut chars = grapheme.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_normalization() {
+    let decomposed = "e\u{301}";
+    assert_eq!(Normalization::None.apply(decomposed), decomposed);
+    assert_eq!(Normalization::Nfc.apply(decomposed), "\u{e9}");
+    assert_eq!(Normalization::Nfc.apply("\u{fb01}"), "\u{fb01}");
+    assert_eq!(Normalization::Nfkc.apply("\u{fb01}"), "fi");
+
+    assert_eq!(Normalization::Nfc.apply_char('\u{212b}'), Some('\u{c5}'));
+    assert_eq!(Normalization::Nfkc.apply_char('\u{fb01}'), None);
+}
+
+#[test]
+fn test_graphemes() {
+    let family = "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}";
+    assert_eq!(graphemes(&format!("a{}e\u{301}", family)).count(), 3);
+    assert_eq!(single_char("a"), Some('a'));
+    assert_eq!(single_char("e\u{301}"), None);
+    assert_eq!(single_char(""), None);
+}
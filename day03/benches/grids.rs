@@ -0,0 +1,62 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use day03::packed::PackedGrid;
+use day03::slopes::{SlopeBounds, SlopeSearch};
+use day03::{count_trees, parse_map};
+
+/// A pseudo random map with roughly one tree in four cells.
+fn random_map(width: usize, height: usize) -> String {
+    let mut state: u64 = 0x2020_0003;
+    let mut map = String::with_capacity((width + 1) * height);
+    for _ in 0..height {
+        for _ in 0..width {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            map.push(if state >> 62 == 0 { '#' } else { '.' });
+        }
+        map.push('\n');
+    }
+    map
+}
+
+fn bench_grids(c: &mut Criterion) {
+    let map = random_map(1000, 10_000);
+    let grid = parse_map(&map).unwrap();
+    let packed = PackedGrid::from_string(&map);
+
+    c.bench_function("parse grid", |b| {
+        b.iter(|| parse_map(black_box(&map)).unwrap())
+    });
+    c.bench_function("parse packed", |b| {
+        b.iter(|| PackedGrid::from_string(black_box(&map)))
+    });
+    c.bench_function("ray grid", |b| {
+        b.iter(|| count_trees(grid.cast_ray((0, 0), black_box((3, 1)))))
+    });
+    c.bench_function("ray packed", |b| {
+        b.iter(|| count_trees(packed.cast_ray((0, 0), black_box((3, 1)))))
+    });
+    c.bench_function("slope packed", |b| {
+        b.iter(|| packed.trees_along_slope(black_box((3, 1))))
+    });
+    c.bench_function("total trees grid", |b| {
+        b.iter(|| {
+            grid.iter()
+                .filter(|cell| **cell == day03::Cell::Tree)
+                .count()
+        })
+    });
+    c.bench_function("total trees packed", |b| {
+        b.iter(|| packed.trees_in_rows(0..packed.height()))
+    });
+    c.bench_function("slope search", |b| {
+        let bounds = SlopeBounds {
+            dx: -50..=50,
+            dy: 1..=10,
+        };
+        b.iter(|| SlopeSearch::new(&grid, black_box(&bounds)))
+    });
+}
+
+criterion_group!(benches, bench_grids);
+criterion_main!(benches);
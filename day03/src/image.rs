@@ -0,0 +1,52 @@
+use crate::{Cell, Grid};
+use grid::{Point2D, Rgb};
+use std::collections::HashSet;
+
+/// Colors for `colorize`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    pub empty: Rgb,
+    pub tree: Rgb,
+    /// Open cells on the overlay, e.g. a ray or path.
+    pub overlay_empty: Rgb,
+    /// Trees on the overlay.
+    pub overlay_tree: Rgb,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette {
+            empty: [250, 250, 250],
+            tree: [34, 119, 51],
+            overlay_empty: [120, 170, 240],
+            overlay_tree: [220, 50, 40],
+        }
+    }
+}
+
+/// The map's colors, with the cells of `overlay` highlighted. Write it with `grid::Grid::write_image`.
+pub fn colorize(grid: &Grid, overlay: &HashSet<Point2D>, palette: &Palette) -> grid::Grid<Rgb> {
+    grid.map(|point, cell| match (cell, overlay.contains(&point)) {
+        (Cell::Empty, false) => palette.empty,
+        (Cell::Tree, false) => palette.tree,
+        (Cell::Empty, true) => palette.overlay_empty,
+        (Cell::Tree, true) => palette.overlay_tree,
+    })
+}
+
+#[test]
+fn test_colorize() {
+    let grid = crate::parse_map(".#\n#.\n").unwrap();
+    let palette = Palette::default();
+    let ray: HashSet<Point2D> = grid.cast_ray((0, 0), (1, 1)).map(|(p, _)| p).collect();
+    let colors = colorize(&grid, &ray, &palette);
+    assert_eq!(
+        colors.iter().cloned().collect::<Vec<Rgb>>(),
+        [
+            palette.empty,
+            palette.tree,
+            palette.tree,
+            palette.overlay_empty
+        ]
+    );
+}
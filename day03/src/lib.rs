@@ -0,0 +1,157 @@
+use grid::{Boundary, CellChar, GridParseError, ParseOptions, Point2D, SymbolTable};
+
+pub mod image;
+pub mod packed;
+pub mod path;
+pub mod slopes;
+
+#[derive(Debug, Clone, std::cmp::PartialEq)]
+pub enum Cell {
+    Empty,
+    Tree,
+}
+
+impl CellChar for Cell {
+    fn from_char(c: char) -> Option<Cell> {
+        match c {
+            '.' => Some(Cell::Empty),
+            '#' => Some(Cell::Tree),
+            _ => None,
+        }
+    }
+
+    fn to_char(&self) -> char {
+        match self {
+            Cell::Empty => '.',
+            Cell::Tree => '#',
+        }
+    }
+}
+
+pub type Grid = grid::Grid<Cell>;
+
+impl Cell {
+    /// The symbols of the puzzle input, see `CellChar`.
+    pub fn symbols() -> SymbolTable<Cell> {
+        SymbolTable::new()
+            .with('.', Cell::Empty)
+            .with('#', Cell::Tree)
+    }
+}
+
+/// The map repeats to the right, so rays wrap around horizontally.
+pub fn parse_map(map_string: &str) -> Result<Grid, GridParseError> {
+    parse_map_with(map_string, &Cell::symbols(), &ParseOptions::default())
+}
+
+/// Like `parse_map`, for maps drawn with other symbols.
+pub fn parse_map_with(
+    map_string: &str,
+    symbols: &SymbolTable<Cell>,
+    options: &ParseOptions,
+) -> Result<Grid, GridParseError> {
+    Grid::parse_with(map_string, options, |c| symbols.get(c))
+        .map(|grid| grid.with_boundary(Boundary::WrapHorizontal))
+}
+
+/// Number of trees among the cells visited by a ray or line.
+pub fn count_trees<'a>(cells: impl Iterator<Item = (Point2D, &'a Cell)>) -> usize {
+    cells.filter(|(_, cell)| **cell == Cell::Tree).count()
+}
+
+#[test]
+fn test_ray_cast() {
+    let grid = parse_map(".#..\n#.#.\n..#.\n").unwrap();
+    let visited_cells: Vec<(Point2D, &Cell)> = grid.cast_ray((0, 0), (1, 1)).collect();
+    assert_eq!(
+        visited_cells,
+        [((1, 1), &Cell::Empty), ((2, 2), &Cell::Tree)]
+    );
+    let visited_cells: Vec<(Point2D, &Cell)> = grid.cast_ray((3, 2), (-1, -1)).collect();
+    assert_eq!(
+        visited_cells,
+        [((2, 1), &Cell::Tree), ((1, 0), &Cell::Tree)]
+    );
+
+    // example from aoc website
+    let grid = parse_map("..##.......\n#...#...#..\n.#....#..#.\n..#.#...#.#\n.#...##..#.\n..#.##.....\n.#.#.#....#\n.#........#\n#.##...#...\n#...##....#\n.#..#...#.#\n").unwrap();
+    let trees: Vec<usize> = [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)]
+        .iter()
+        .map(|direction| count_trees(grid.cast_ray((0, 0), *direction)))
+        .collect();
+    assert_eq!(trees, [2, 7, 3, 4, 2]);
+    // the line also visits the row skipped by the ray
+    assert_eq!(count_trees(grid.cast_line((0, 0), (1, 2))), 4);
+}
+
+#[test]
+fn test_grid_access() {
+    assert_eq!(parse_map("#").unwrap()[(0, 0)], Cell::Tree);
+    let grid = parse_map(".#..\n#.#.\n..#.\n").unwrap();
+    assert_eq!(grid[(0, 0)], Cell::Empty);
+    assert_eq!(grid[(1, 0)], Cell::Tree);
+    assert_eq!(grid[(2, 0)], Cell::Empty);
+    assert_eq!(grid[(0, 1)], Cell::Tree);
+    assert_eq!(grid[(2, 2)], Cell::Tree);
+    assert_eq!(grid[(3, 2)], Cell::Empty);
+}
+
+#[test]
+fn test_grid_from_string() {
+    let wrapping = |grid: Grid| grid.with_boundary(Boundary::WrapHorizontal);
+    assert_eq!(
+        parse_map("").unwrap(),
+        wrapping(Grid::new(0, 0, [].to_vec()))
+    );
+    assert_eq!(
+        parse_map(".").unwrap(),
+        wrapping(Grid::new(1, 1, [Cell::Empty].to_vec()))
+    );
+    assert_eq!(
+        parse_map("#").unwrap(),
+        wrapping(Grid::new(1, 1, [Cell::Tree].to_vec()))
+    );
+    assert_eq!(
+        parse_map(".#..\n#.#.\n..#.\n").unwrap(),
+        wrapping(Grid::new(
+            4,
+            3,
+            [
+                Cell::Empty,
+                Cell::Tree,
+                Cell::Empty,
+                Cell::Empty,
+                Cell::Tree,
+                Cell::Empty,
+                Cell::Tree,
+                Cell::Empty,
+                Cell::Empty,
+                Cell::Empty,
+                Cell::Tree,
+                Cell::Empty
+            ]
+            .to_vec()
+        ))
+    );
+}
+
+#[test]
+fn test_parse_map_errors_and_symbols() {
+    assert_eq!(
+        parse_map(".#\n.O\n"),
+        Err(GridParseError::InvalidChar {
+            row: 2,
+            column: 2,
+            found: 'O'
+        })
+    );
+    let symbols = Cell::symbols().with('O', Cell::Tree).with('X', Cell::Tree);
+    let options = ParseOptions {
+        trim_trailing_whitespace: true,
+        ..ParseOptions::default()
+    };
+    assert_eq!(
+        parse_map_with(".#  \r\nXO\r\n", &symbols, &options),
+        parse_map(".#\n##\n")
+    );
+}
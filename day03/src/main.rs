@@ -1,83 +1,121 @@
+use day03::image::{colorize, Palette};
+use day03::path::{find_path, DOWN_LEFT_RIGHT, KING_MOVES};
+use day03::slopes::{Objective, SlopeBounds, SlopeSearch};
+use day03::{count_trees, parse_map_with, Cell, Grid};
+use grid::{ImageFormat, ParseOptions, Point2D};
+use std::collections::HashSet;
+use std::env;
 use std::fs::File;
 use std::io;
 use std::io::prelude::*;
-use std::ops::Index;
+use std::io::BufWriter;
+use std::path::Path;
+use std::process;
 
-#[derive(Debug, Clone, std::cmp::PartialEq)]
-pub enum Cell {
-    Empty,
-    Tree,
-}
-
-pub type Point2D = (usize, usize);
+fn print_fixed_slopes(grid: &Grid) {
+    let mut trees_product = 1;
 
-#[derive(Debug, std::cmp::PartialEq)]
-pub struct Grid {
-    cells: Vec<Cell>,
-    width: usize,
-    height: usize,
+    for direction in &[(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)] {
+        let nr_trees_encountered = count_trees(grid.cast_ray((0, 0), *direction));
+        println!(
+            "Direction {:?}: Found {} trees on the way.",
+            direction, nr_trees_encountered
+        );
+        trees_product *= nr_trees_encountered;
+    }
+    println!("Product: {}", trees_product);
 }
 
-impl Grid {
-    pub fn from_string(grid_string: &str) -> Grid {
-        let mut cells = Vec::new();
-        let mut width = 0;
-        let mut height = 0;
-
-        for grid_line in grid_string.lines() {
-            height += 1;
-            let mut line_length = 0;
-            for cell_char in grid_line.chars() {
-                line_length += 1;
-                match cell_char {
-                    '.' => cells.push(Cell::Empty),
-                    '#' => cells.push(Cell::Tree),
-                    _ => assert!(false),
-                }
-            }
-            assert!(width == 0 || width == line_length);
-            width = line_length;
-        }
-
-        Grid {
-            cells,
-            width,
-            height,
-        }
+/// `search <max_dx> <max_dy> [k]`
+fn print_search(grid: &Grid, args: &[String]) {
+    let max_dx: isize = args[0].parse().expect("invalid max dx");
+    let max_dy: usize = args[1].parse().expect("invalid max dy");
+    let k: usize = args.get(2).map_or(5, |k| k.parse().expect("invalid k"));
+
+    let search = SlopeSearch::new(
+        grid,
+        &SlopeBounds {
+            dx: -max_dx..=max_dx,
+            dy: 1..=max_dy,
+        },
+    );
+    println!("Evaluated {} slopes.", search.counts.len());
+    for (name, counts) in &[("Fewest", search.min()), ("Most", search.max())] {
+        let slopes: Vec<_> = counts.iter().map(|count| count.slope).collect();
+        println!(
+            "{} trees ({}): {:?}",
+            name,
+            counts.first().map_or(0, |count| count.trees),
+            slopes
+        );
     }
-
-    pub fn cast_ray(self, start: Point2D, direction: Point2D) -> Ray {
-        Ray {
-            grid: self,
-            current: start,
-            direction,
+    for objective in &[Objective::Minimize, Objective::Maximize] {
+        let (slopes, product) = search.best_product(k, *objective);
+        let slopes: Vec<_> = slopes.iter().map(|count| count.slope).collect();
+        match product {
+            Some(product) => println!(
+                "{:?} product of {} slopes: {} {:?}",
+                objective, k, product, slopes
+            ),
+            None => println!(
+                "{:?} product of {} slopes overflows: {:?}",
+                objective, k, slopes
+            ),
         }
     }
 }
 
-impl Index<Point2D> for Grid {
-    type Output = Cell;
-    fn index(&self, point: Point2D) -> &Self::Output {
-        &self.cells[point.0 + self.width * point.1]
+/// `path [king]`
+fn print_path(grid: &Grid, args: &[String]) {
+    let moves: &[_] = match args.first().map(|moves| moves.as_str()) {
+        Some("king") => &KING_MOVES,
+        _ => &DOWN_LEFT_RIGHT,
+    };
+    match find_path(grid, moves) {
+        Some(path) => {
+            print!("{}", path.render(grid));
+            println!(
+                "Path with {} steps hits {} trees.",
+                path.points.len() - 1,
+                path.trees
+            );
+        }
+        None => println!("There is no path to the bottom."),
     }
 }
 
-pub struct Ray {
-    grid: Grid,
-    current: Point2D,
-    direction: Point2D,
-}
-
-impl Iterator for Ray {
-    type Item = Cell;
-    fn next(&mut self) -> Option<Self::Item> {
-        self.current.0 = (self.current.0 + self.direction.0) % self.grid.width;
-        self.current.1 = self.current.1 + self.direction.1;
-        match self.current.1 >= self.grid.height {
-            true => None,
-            false => Some(self.grid[self.current].clone()),
+/// `image <file> [cell_size] [path [king] | ray <dx> <dy>]`, the format is taken from the file extension.
+fn write_image(grid: &Grid, args: &[String]) -> io::Result<()> {
+    let path = Path::new(&args[0]);
+    let format = ImageFormat::from_path(path).expect("unknown image format, use png, ppm or svg");
+    let cell_size: usize = args
+        .get(1)
+        .map_or(4, |size| size.parse().expect("invalid cell size"));
+    let overlay: HashSet<Point2D> = match args.get(2).map(|overlay| overlay.as_str()) {
+        Some("path") => {
+            let moves: &[_] = match args.get(3).map(|moves| moves.as_str()) {
+                Some("king") => &KING_MOVES,
+                _ => &DOWN_LEFT_RIGHT,
+            };
+            find_path(grid, moves)
+                .map(|path| path.points.into_iter().collect())
+                .unwrap_or_default()
         }
-    }
+        Some("ray") => {
+            let dx = args[3].parse().expect("invalid dx");
+            let dy = args[4].parse().expect("invalid dy");
+            let mut points: HashSet<Point2D> = grid
+                .cast_ray((0, 0), (dx, dy))
+                .map(|(point, _)| point)
+                .collect();
+            points.insert((0, 0));
+            points
+        }
+        _ => HashSet::new(),
+    };
+    let mut writer = BufWriter::new(File::create(path)?);
+    colorize(grid, &overlay, &Palette::default()).write_image(format, cell_size, &mut writer)?;
+    writer.flush()
 }
 
 fn main() -> io::Result<()> {
@@ -85,99 +123,33 @@ fn main() -> io::Result<()> {
     let mut input_string = String::new();
     file.read_to_string(&mut input_string)?;
 
-    let mut trees_product = 1;
-
-    for direction in &[(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)] {
-        let grid = Grid::from_string(&input_string);
-        let nr_trees_encountered = grid
-            .cast_ray((0, 0), *direction)
-            .filter(|c| *c == Cell::Tree)
-            .count();
-        println!(
-            "Direction {:?}: Found {} trees on the way.",
-            direction, nr_trees_encountered
-        );
-        trees_product *= nr_trees_encountered;
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    // `--trees <chars>` adds more tree symbols
+    let mut symbols = Cell::symbols();
+    if let Some(flag_id) = args.iter().position(|arg| arg == "--trees") {
+        args.remove(flag_id);
+        assert!(flag_id < args.len(), "missing chars for --trees");
+        for symbol in args.remove(flag_id).chars() {
+            symbols = symbols.with(symbol, Cell::Tree);
+        }
+    }
+    let options = ParseOptions {
+        trim_trailing_whitespace: true,
+        ..ParseOptions::default()
+    };
+    let grid = match parse_map_with(&input_string, &symbols, &options) {
+        Ok(grid) => grid,
+        Err(error) => {
+            eprintln!("Invalid map: {}", error);
+            process::exit(1);
+        }
+    };
+    match args.first().map(|mode| mode.as_str()) {
+        Some("search") => print_search(&grid, &args[1..]),
+        Some("path") => print_path(&grid, &args[1..]),
+        Some("image") => write_image(&grid, &args[1..])?,
+        _ => print_fixed_slopes(&grid),
     }
-    println!("Product: {}", trees_product);
 
     Ok(())
 }
-
-#[test]
-fn test_ray_cast() {
-    let grid = Grid::from_string(".#..\n#.#.\n..#.\n");
-    let visited_cells: Vec<Cell> = grid.cast_ray((0, 0), (1, 1)).collect();
-    assert_eq!(visited_cells, [Cell::Empty, Cell::Tree]);
-
-    // example from aoc website
-    let grid = Grid::from_string("..##.......\n#...#...#..\n.#....#..#.\n..#.#...#.#\n.#...##..#.\n..#.##.....\n.#.#.#....#\n.#........#\n#.##...#...\n#...##....#\n.#..#...#.#\n");
-    assert_eq!(
-        grid.cast_ray((0, 0), (3, 1))
-            .filter(|c| *c == Cell::Tree)
-            .count(),
-        7
-    );
-}
-
-#[test]
-fn test_grid_access() {
-    assert_eq!(Grid::from_string("#")[(0, 0)], Cell::Tree);
-    let grid = Grid::from_string(".#..\n#.#.\n..#.\n");
-    assert_eq!(grid[(0, 0)], Cell::Empty);
-    assert_eq!(grid[(1, 0)], Cell::Tree);
-    assert_eq!(grid[(2, 0)], Cell::Empty);
-    assert_eq!(grid[(0, 1)], Cell::Tree);
-    assert_eq!(grid[(2, 2)], Cell::Tree);
-    assert_eq!(grid[(3, 2)], Cell::Empty);
-}
-
-#[test]
-fn test_grid_from_string() {
-    assert_eq!(
-        Grid::from_string(""),
-        Grid {
-            cells: [].to_vec(),
-            width: 0,
-            height: 0
-        }
-    );
-    assert_eq!(
-        Grid::from_string("."),
-        Grid {
-            cells: [Cell::Empty].to_vec(),
-            width: 1,
-            height: 1
-        }
-    );
-    assert_eq!(
-        Grid::from_string("#"),
-        Grid {
-            cells: [Cell::Tree].to_vec(),
-            width: 1,
-            height: 1
-        }
-    );
-    assert_eq!(
-        Grid::from_string(".#..\n#.#.\n..#.\n"),
-        Grid {
-            cells: [
-                Cell::Empty,
-                Cell::Tree,
-                Cell::Empty,
-                Cell::Empty,
-                Cell::Tree,
-                Cell::Empty,
-                Cell::Tree,
-                Cell::Empty,
-                Cell::Empty,
-                Cell::Empty,
-                Cell::Tree,
-                Cell::Empty
-            ]
-            .to_vec(),
-            width: 4,
-            height: 3
-        }
-    );
-}
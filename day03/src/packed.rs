@@ -0,0 +1,285 @@
+use crate::{Cell, Grid};
+use grid::{Direction, GridParseError, Point2D};
+use std::ops::{Index, Range};
+
+/// A map with one tree bit per cell, rows padded to whole `u64` words.
+///
+/// Wraps horizontally like `parse_map`, and can be used in place of `Grid` for huge maps.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackedGrid {
+    words: Vec<u64>,
+    words_per_row: usize,
+    width: usize,
+    height: usize,
+}
+
+impl PackedGrid {
+    pub fn new(grid: &Grid) -> PackedGrid {
+        let mut packed = PackedGrid::empty(grid.width());
+        for row in grid.rows() {
+            packed.push_row(row.iter().map(|cell| *cell == Cell::Tree));
+        }
+        packed
+    }
+
+    /// Parses a map without going through `Grid`, like `parse_map` with `ParseOptions::default()`.
+    pub fn parse(map_string: &str) -> Result<PackedGrid, GridParseError> {
+        let mut packed = PackedGrid::empty(0);
+        for (line_id, line) in map_string.split('\n').enumerate() {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            if line.is_empty() {
+                continue;
+            }
+            if let Some((column, found)) = line
+                .chars()
+                .enumerate()
+                .find(|(_, c)| *c != '#' && *c != '.')
+            {
+                return Err(GridParseError::InvalidChar {
+                    row: line_id + 1,
+                    column: column + 1,
+                    found,
+                });
+            }
+            // valid rows are ASCII, so bytes are cells
+            if packed.height == 0 {
+                packed = PackedGrid::empty(line.len());
+            } else if line.len() != packed.width {
+                return Err(GridParseError::RaggedRow {
+                    row: line_id + 1,
+                    expected_width: packed.width,
+                    width: line.len(),
+                });
+            }
+            packed.push_row(line.bytes().map(|b| b == b'#'));
+        }
+        Ok(packed)
+    }
+
+    /// Like `parse`, but panics on invalid input.
+    pub fn from_string(map_string: &str) -> PackedGrid {
+        PackedGrid::parse(map_string).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    fn empty(width: usize) -> PackedGrid {
+        PackedGrid {
+            words: Vec::new(),
+            words_per_row: width.div_ceil(64),
+            width,
+            height: 0,
+        }
+    }
+
+    fn push_row(&mut self, trees: impl Iterator<Item = bool>) {
+        let mut word = 0;
+        let mut x = 0;
+        for tree in trees {
+            word |= (tree as u64) << (x % 64);
+            x += 1;
+            if x % 64 == 0 {
+                self.words.push(word);
+                word = 0;
+            }
+        }
+        if x % 64 != 0 {
+            self.words.push(word);
+        }
+        self.height += 1;
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn row(&self, y: usize) -> &[u64] {
+        &self.words[y * self.words_per_row..(y + 1) * self.words_per_row]
+    }
+
+    pub fn is_tree(&self, point: Point2D) -> bool {
+        assert!(point.0 < self.width, "column out of range");
+        self.words[point.1 * self.words_per_row + point.0 / 64] & (1 << (point.0 % 64)) != 0
+    }
+
+    /// Trees in row `y` within the columns `xs`.
+    pub fn trees_in_row(&self, y: usize, xs: Range<usize>) -> usize {
+        assert!(xs.end <= self.width, "columns out of range");
+        if xs.start >= xs.end {
+            return 0;
+        }
+        let row = self.row(y);
+        let (first, last) = (xs.start / 64, (xs.end - 1) / 64);
+        let first_mask = u64::MAX << (xs.start % 64);
+        let last_mask = u64::MAX >> (63 - (xs.end - 1) % 64);
+        if first == last {
+            return (row[first] & first_mask & last_mask).count_ones() as usize;
+        }
+        let inner: u32 = row[first + 1..last]
+            .iter()
+            .map(|word| word.count_ones())
+            .sum();
+        ((row[first] & first_mask).count_ones() + inner + (row[last] & last_mask).count_ones())
+            as usize
+    }
+
+    /// Trees in all of the rows `ys`.
+    pub fn trees_in_rows(&self, ys: Range<usize>) -> usize {
+        let words = &self.words[ys.start * self.words_per_row..ys.end * self.words_per_row];
+        words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// Trees on the way from the top left to the bottom, like `count_trees` for a ray with the given slope.
+    ///
+    /// Panics if `slope.1` isn't positive.
+    pub fn trees_along_slope(&self, slope: Direction) -> usize {
+        assert!(slope.1 > 0, "slope has to go down");
+        if self.width == 0 {
+            return 0;
+        }
+        let dx = slope.0.rem_euclid(self.width as isize) as usize;
+        let dy = slope.1 as usize;
+        let mut x = 0;
+        let mut trees = 0;
+        for row_start in
+            (dy * self.words_per_row..self.words.len()).step_by(dy * self.words_per_row)
+        {
+            x += dx;
+            if x >= self.width {
+                x -= self.width;
+            }
+            trees += (self.words[row_start + x / 64] >> (x % 64)) as usize & 1;
+        }
+        trees
+    }
+
+    /// Same as `Grid::cast_ray` on a horizontally wrapping grid.
+    pub fn cast_ray(&self, start: Point2D, direction: Direction) -> PackedRay<'_> {
+        assert!(direction != (0, 0), "ray direction must not be (0, 0)");
+        let start = (start.0 % self.width.max(1), start.1);
+        PackedRay {
+            grid: self,
+            start_x: start.0,
+            current: Some(start),
+            dx: match self.width {
+                0 => 0,
+                width => direction.0.rem_euclid(width as isize) as usize,
+            },
+            dy: direction.1,
+        }
+    }
+}
+
+impl Index<Point2D> for PackedGrid {
+    type Output = Cell;
+    fn index(&self, point: Point2D) -> &Self::Output {
+        match self.is_tree(point) {
+            true => &Cell::Tree,
+            false => &Cell::Empty,
+        }
+    }
+}
+
+pub struct PackedRay<'a> {
+    grid: &'a PackedGrid,
+    /// Horizontal rays end before they are back here.
+    start_x: usize,
+    current: Option<Point2D>,
+    /// Always within the width, so that wrapping is a single subtraction.
+    dx: usize,
+    dy: isize,
+}
+
+impl<'a> Iterator for PackedRay<'a> {
+    type Item = (Point2D, &'a Cell);
+    fn next(&mut self) -> Option<Self::Item> {
+        let (mut x, y) = self.current?;
+        let y = y as isize + self.dy;
+        if self.grid.width == 0 || y < 0 || y as usize >= self.grid.height {
+            self.current = None;
+            return None;
+        }
+        x += self.dx;
+        if x >= self.grid.width {
+            x -= self.grid.width;
+        }
+        if self.dy == 0 && x == self.start_x {
+            self.current = None;
+            return None;
+        }
+        let point = (x, y as usize);
+        self.current = Some(point);
+        Some((point, &self.grid[point]))
+    }
+}
+
+#[cfg(test)]
+fn example_map() -> &'static str {
+    "..##.......\n#...#...#..\n.#....#..#.\n..#.#...#.#\n.#...##..#.\n..#.##.....\n.#.#.#....#\n.#........#\n#.##...#...\n#...##....#\n.#..#...#.#\n"
+}
+
+#[test]
+fn test_packed_matches_grid() {
+    let grid = crate::parse_map(example_map()).unwrap();
+    let packed = PackedGrid::from_string(example_map());
+    assert_eq!(packed, PackedGrid::new(&grid));
+    assert_eq!((packed.width(), packed.height()), (11, 11));
+    for point in grid.points() {
+        assert_eq!(packed[point], grid[point]);
+    }
+    for dy in -3..4 {
+        for dx in -12..12 {
+            if (dx, dy) == (0, 0) {
+                continue;
+            }
+            let expected: Vec<_> = grid.cast_ray((2, 5), (dx, dy)).collect();
+            let rays: Vec<_> = packed.cast_ray((2, 5), (dx, dy)).collect();
+            assert_eq!(rays, expected, "direction ({}, {})", dx, dy);
+            if dy > 0 {
+                assert_eq!(
+                    packed.trees_along_slope((dx, dy)),
+                    crate::count_trees(grid.cast_ray((0, 0), (dx, dy)))
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_popcount_queries() {
+    let packed = PackedGrid::from_string(example_map());
+    assert_eq!(packed.trees_in_row(0, 0..11), 2);
+    assert_eq!(packed.trees_in_row(0, 3..3), 0);
+    assert_eq!(packed.trees_in_row(3, 4..11), 3);
+    assert_eq!(packed.trees_in_rows(0..2), 5);
+    assert_eq!(
+        packed.trees_in_rows(0..11),
+        example_map().matches('#').count()
+    );
+
+    let wide = PackedGrid::from_string(&format!("{}\n{}#\n", "#".repeat(200), ".".repeat(199)));
+    assert_eq!(wide.trees_in_row(0, 0..200), 200);
+    assert_eq!(wide.trees_in_row(0, 63..129), 66);
+    assert_eq!(wide.trees_in_row(0, 64..128), 64);
+    assert_eq!(wide.trees_in_row(1, 150..200), 1);
+    assert_eq!(wide.trees_along_slope((199, 1)), 1);
+    assert_eq!(wide.trees_along_slope((-1, 1)), 1);
+}
+
+#[test]
+#[should_panic(expected = "invalid cell char 'ä' at row 2, column 2")]
+fn test_from_string_invalid_char() {
+    PackedGrid::from_string("..\n.ä");
+}
+
+#[test]
+fn test_parse_errors_match_grid() {
+    for input in &["..\n.ä", "..\r\n.#\r\n", "..\n\n...\n", "#\n\n.\r\n#"] {
+        assert_eq!(
+            PackedGrid::parse(input),
+            crate::parse_map(input).map(|grid| PackedGrid::new(&grid))
+        );
+    }
+}
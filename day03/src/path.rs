@@ -0,0 +1,135 @@
+use crate::{Cell, Grid};
+use grid::{CellChar, Direction, Point2D};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// One step down, left or right.
+pub const DOWN_LEFT_RIGHT: [Direction; 3] = [(0, 1), (-1, 0), (1, 0)];
+
+/// One step in any of the 8 directions.
+pub const KING_MOVES: [Direction; 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    /// From a cell in the top row to a cell in the bottom row.
+    pub points: Vec<Point2D>,
+    /// Trees on the path, including the first and last cell.
+    pub trees: usize,
+}
+
+impl Path {
+    /// The map with the path drawn onto it, `O` for open cells and `X` for trees on the path.
+    pub fn render(&self, grid: &Grid) -> String {
+        let mut rows: Vec<Vec<char>> = grid
+            .rows()
+            .map(|row| row.iter().map(|cell| cell.to_char()).collect())
+            .collect();
+        for point in &self.points {
+            rows[point.1][point.0] = match grid[point] {
+                Cell::Empty => 'O',
+                Cell::Tree => 'X',
+            };
+        }
+        rows.into_iter()
+            .map(|row| row.into_iter().collect::<String>() + "\n")
+            .collect()
+    }
+}
+
+/// Finds a path from the top row to the bottom row that hits the fewest trees, and out of those,
+/// takes the fewest steps. Each step is one of `moves`, resolved with the grid's boundary,
+/// so `parse_map` grids wrap around horizontally.
+///
+/// Returns `None` if the bottom row can't be reached.
+pub fn find_path(grid: &Grid, moves: &[Direction]) -> Option<Path> {
+    let width = grid.width();
+    let index = |point: Point2D| point.0 + point.1 * width;
+    let tree_cost = |point: Point2D| (grid[point] == Cell::Tree) as usize;
+
+    // (trees, steps), Dijkstra with lexicographic costs
+    let mut costs = vec![(usize::MAX, usize::MAX); width * grid.height()];
+    let mut previous: Vec<Option<Point2D>> = vec![None; costs.len()];
+    let mut queue = BinaryHeap::new();
+    for start in grid.points().take(width) {
+        let cost = (tree_cost(start), 0);
+        costs[index(start)] = cost;
+        queue.push(Reverse((cost, start)));
+    }
+
+    while let Some(Reverse((cost, point))) = queue.pop() {
+        if cost > costs[index(point)] {
+            continue; // already reached more cheaply
+        }
+        if point.1 + 1 == grid.height() {
+            let mut points = vec![point];
+            while let Some(before) = previous[index(*points.last().unwrap())] {
+                points.push(before);
+            }
+            points.reverse();
+            return Some(Path {
+                points,
+                trees: cost.0,
+            });
+        }
+        for (dx, dy) in moves {
+            if let Some(next) = grid.offset(point, *dx, *dy) {
+                let next_cost = (cost.0 + tree_cost(next), cost.1 + 1);
+                if next_cost < costs[index(next)] {
+                    costs[index(next)] = next_cost;
+                    previous[index(next)] = Some(point);
+                    queue.push(Reverse((next_cost, next)));
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+fn example_grid() -> Grid {
+    crate::parse_map("..##.......\n#...#...#..\n.#....#..#.\n..#.#...#.#\n.#...##..#.\n..#.##.....\n.#.#.#....#\n.#........#\n#.##...#...\n#...##....#\n.#..#...#.#\n").unwrap()
+}
+
+#[test]
+fn test_find_path() {
+    let grid = example_grid();
+    // straight down, every column has a tree, and column 7 is the only one with just one (in row 8)
+    let path = find_path(&grid, &[(0, 1)]).unwrap();
+    assert_eq!(path.trees, 1);
+    assert_eq!(path.points.len(), 11);
+
+    let path = find_path(&grid, &DOWN_LEFT_RIGHT).unwrap();
+    assert_eq!(path.trees, 0);
+    assert_eq!(path.points.first().unwrap().1, 0);
+    assert_eq!(path.points.last().unwrap().1, 10);
+    for step in path.points.windows(2) {
+        assert!(DOWN_LEFT_RIGHT
+            .iter()
+            .any(|(dx, dy)| grid.offset(step[0], *dx, *dy) == Some(step[1])));
+    }
+    assert!(find_path(&grid, &KING_MOVES).unwrap().points.len() <= path.points.len());
+}
+
+#[test]
+fn test_find_path_wraps_and_blocks() {
+    // the only gap in the middle row is reached by wrapping around to the left
+    let grid = crate::parse_map(".##\n##.\n.##\n").unwrap();
+    let path = find_path(&grid, &DOWN_LEFT_RIGHT).unwrap();
+    assert_eq!(path.trees, 1);
+    let path = find_path(&grid, &KING_MOVES).unwrap();
+    assert_eq!(path.points, [(0, 0), (2, 1), (0, 2)]);
+    assert_eq!(path.trees, 0);
+    assert_eq!(path.render(&grid), "O##\n##O\nO##\n");
+
+    assert_eq!(find_path(&grid, &[(1, 0)]), None);
+    assert_eq!(find_path(&crate::parse_map("").unwrap(), &KING_MOVES), None);
+}
@@ -0,0 +1,118 @@
+use crate::packed::PackedGrid;
+use crate::Grid;
+use grid::Direction;
+use std::ops::RangeInclusive;
+
+/// The slopes to search, `dy` has to be positive so that every slope reaches the bottom.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlopeBounds {
+    pub dx: RangeInclusive<isize>,
+    pub dy: RangeInclusive<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SlopeCount {
+    pub slope: Direction,
+    pub trees: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Objective {
+    Minimize,
+    Maximize,
+}
+
+pub struct SlopeSearch {
+    /// Every slope within the bounds, sorted by `dy`, then `dx`.
+    pub counts: Vec<SlopeCount>,
+}
+
+impl SlopeSearch {
+    pub fn new(grid: &Grid, bounds: &SlopeBounds) -> SlopeSearch {
+        let trees = PackedGrid::new(grid);
+        let mut counts = Vec::new();
+        for dy in bounds.dy.clone().filter(|dy| *dy > 0) {
+            for dx in bounds.dx.clone() {
+                let slope = (dx, dy as isize);
+                counts.push(SlopeCount {
+                    slope,
+                    trees: trees.trees_along_slope(slope),
+                });
+            }
+        }
+        SlopeSearch { counts }
+    }
+
+    /// All slopes with the fewest trees.
+    pub fn min(&self) -> Vec<SlopeCount> {
+        self.extremes(Objective::Minimize)
+    }
+
+    /// All slopes with the most trees.
+    pub fn max(&self) -> Vec<SlopeCount> {
+        self.extremes(Objective::Maximize)
+    }
+
+    fn extremes(&self, objective: Objective) -> Vec<SlopeCount> {
+        let trees = self.counts.iter().map(|count| count.trees);
+        let best = match objective {
+            Objective::Minimize => trees.min(),
+            Objective::Maximize => trees.max(),
+        };
+        self.counts
+            .iter()
+            .filter(|count| Some(count.trees) == best)
+            .cloned()
+            .collect()
+    }
+
+    /// The k slopes whose tree counts have the smallest or largest product, and that product.
+    /// The product is `None` if it doesn't fit into a `u128`.
+    ///
+    /// As tree counts aren't negative, these are just the k slopes with the fewest or most trees.
+    pub fn best_product(&self, k: usize, objective: Objective) -> (Vec<SlopeCount>, Option<u128>) {
+        let mut sorted = self.counts.clone();
+        // stable, so ties keep the order of `counts`
+        match objective {
+            Objective::Minimize => sorted.sort_by_key(|count| count.trees),
+            Objective::Maximize => sorted.sort_by_key(|count| std::cmp::Reverse(count.trees)),
+        }
+        sorted.truncate(k);
+        let product = sorted.iter().try_fold(1u128, |product, count| {
+            product.checked_mul(count.trees as u128)
+        });
+        (sorted, product)
+    }
+}
+
+#[cfg(test)]
+fn example_grid() -> Grid {
+    crate::parse_map("..##.......\n#...#...#..\n.#....#..#.\n..#.#...#.#\n.#...##..#.\n..#.##.....\n.#.#.#....#\n.#........#\n#.##...#...\n#...##....#\n.#..#...#.#\n").unwrap()
+}
+
+#[test]
+fn test_slope_search() {
+    let search = SlopeSearch::new(
+        &example_grid(),
+        &SlopeBounds {
+            dx: 0..=7,
+            dy: 0..=2,
+        },
+    );
+    assert_eq!(search.counts.len(), 16);
+    assert_eq!(
+        search.max(),
+        [SlopeCount {
+            slope: (3, 1),
+            trees: 7
+        }]
+    );
+    assert!(search.min().iter().all(|count| count.trees == 0));
+
+    let (slopes, product) = search.best_product(2, Objective::Maximize);
+    assert_eq!(slopes[0].slope, (3, 1));
+    assert_eq!(slopes[1].trees, 4);
+    assert_eq!(product, Some(28));
+    let (_, product) = search.best_product(3, Objective::Minimize);
+    assert_eq!(product, Some(0));
+}
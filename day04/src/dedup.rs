@@ -0,0 +1,245 @@
+use crate::records::Record;
+use std::collections::{BTreeMap, HashMap};
+
+/// Records sharing a passport id.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateGroup {
+    pub passport_id: String,
+    /// See `Record::index`.
+    pub records: Vec<usize>,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// A field with different values among records.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    pub key: String,
+    /// Record index and value, `None` if the record doesn't have the field.
+    pub values: Vec<(usize, Option<String>)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeCandidate {
+    /// Record indices, the smaller one first.
+    pub records: (usize, usize),
+    /// Edit distance between the passport ids, 0 for exact duplicates.
+    pub pid_distance: usize,
+    /// Between 0 and 1, see `find_merge_candidates`.
+    pub confidence: f64,
+    /// Keys of fields present in both records with different values.
+    pub conflicting_keys: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DedupOptions {
+    /// The largest edit distance between passport ids that is still a probable typo.
+    pub max_pid_distance: usize,
+    /// Candidates with a lower confidence are dropped.
+    pub min_confidence: f64,
+}
+
+impl Default for DedupOptions {
+    fn default() -> Self {
+        DedupOptions {
+            max_pid_distance: 2,
+            min_confidence: 0.5,
+        }
+    }
+}
+
+/// Levenshtein distance over chars.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + (a_char != *b_char) as usize;
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Conflicting fields among the records, keys in order of first appearance.
+fn conflicts(records: &[&Record]) -> Vec<Conflict> {
+    let mut keys: Vec<&str> = Vec::new();
+    for record in records {
+        for key in record.passport.keys() {
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+    }
+    keys.into_iter()
+        .filter_map(|key| {
+            let values: Vec<(usize, Option<String>)> = records
+                .iter()
+                .map(|record| {
+                    let value = record.passport.get(key).map(|value| value.to_string());
+                    (record.index, value)
+                })
+                .collect();
+            match values.iter().all(|(_, value)| *value == values[0].1) {
+                true => None,
+                false => Some(Conflict {
+                    key: key.to_string(),
+                    values,
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Groups records with the same passport id, ordered by id. Records without `pid` are skipped.
+pub fn find_duplicates(records: &[Record]) -> Vec<DuplicateGroup> {
+    let mut by_id: BTreeMap<&str, Vec<&Record>> = BTreeMap::new();
+    for record in records {
+        if let Some(pid) = record.passport.get("pid") {
+            by_id.entry(pid).or_default().push(record);
+        }
+    }
+    by_id
+        .into_iter()
+        .filter(|(_, group)| group.len() > 1)
+        .map(|(pid, group)| DuplicateGroup {
+            passport_id: pid.to_string(),
+            records: group.iter().map(|record| record.index).collect(),
+            conflicts: conflicts(&group),
+        })
+        .collect()
+}
+
+/// Pairs of records that are probably the same person: equal birth year and eye color,
+/// and passport ids at most `max_pid_distance` edits apart.
+///
+/// The confidence weighs the passport id similarity (`1 - distance / length`) with 0.6,
+/// and the share of other fields present in both records that agree with 0.4. `cid` is ignored.
+/// Sorted by confidence, highest first.
+pub fn find_merge_candidates(records: &[Record], options: &DedupOptions) -> Vec<MergeCandidate> {
+    // equal birth year and eye color are required, so only records within a bucket are compared
+    let mut buckets: HashMap<(&str, &str), Vec<&Record>> = HashMap::new();
+    for record in records {
+        let passport = &record.passport;
+        if let (Some(byr), Some(ecl), Some(_)) = (
+            passport.get("byr"),
+            passport.get("ecl"),
+            passport.get("pid"),
+        ) {
+            buckets.entry((byr, ecl)).or_default().push(record);
+        }
+    }
+
+    let mut candidates = Vec::new();
+    for bucket in buckets.values() {
+        for (i, a) in bucket.iter().enumerate() {
+            for b in &bucket[i + 1..] {
+                let (pid_a, pid_b) = (
+                    a.passport.get("pid").unwrap(),
+                    b.passport.get("pid").unwrap(),
+                );
+                let pid_distance = edit_distance(pid_a, pid_b);
+                if pid_distance > options.max_pid_distance {
+                    continue;
+                }
+                let pid_length = pid_a.chars().count().max(pid_b.chars().count()).max(1);
+                let pid_similarity = 1.0 - pid_distance as f64 / pid_length as f64;
+
+                let shared: Vec<&str> = a
+                    .passport
+                    .keys()
+                    .filter(|key| *key != "pid" && *key != "cid")
+                    .filter(|key| b.passport.get(key).is_some())
+                    .collect();
+                let conflicting_keys: Vec<String> = shared
+                    .iter()
+                    .filter(|key| a.passport.get(key) != b.passport.get(key))
+                    .map(|key| key.to_string())
+                    .collect();
+                let agreement = 1.0 - conflicting_keys.len() as f64 / shared.len() as f64;
+
+                let confidence = 0.6 * pid_similarity + 0.4 * agreement;
+                if confidence >= options.min_confidence {
+                    candidates.push(MergeCandidate {
+                        records: (a.index.min(b.index), a.index.max(b.index)),
+                        pid_distance,
+                        confidence,
+                        conflicting_keys,
+                    });
+                }
+            }
+        }
+    }
+    candidates.sort_by(|a, b| {
+        b.confidence
+            .partial_cmp(&a.confidence)
+            .unwrap()
+            .then(a.records.cmp(&b.records))
+    });
+    candidates
+}
+
+#[cfg(test)]
+fn example_records() -> Vec<Record> {
+    let input = "pid:087499704 byr:1980 ecl:grn hgt:74in iyr:2012\n\n\
+                 pid:087499704 byr:1980 ecl:grn hgt:75in iyr:2012 cid:1\n\n\
+                 pid:087499740 byr:1980 ecl:grn hgt:74in iyr:2012\n\n\
+                 pid:187499704 byr:1981 ecl:grn hgt:74in\n\n\
+                 pid:999999999 byr:1980 ecl:grn hgt:74in iyr:2012\n\n\
+                 byr:1980 ecl:grn";
+    crate::records::read_records(input, &Default::default())
+        .unwrap()
+        .0
+}
+
+#[test]
+fn test_edit_distance() {
+    assert_eq!(edit_distance("087499704", "087499704"), 0);
+    assert_eq!(edit_distance("087499704", "087499740"), 2);
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+    assert_eq!(edit_distance("", "abc"), 3);
+}
+
+#[test]
+fn test_find_duplicates() {
+    let groups = find_duplicates(&example_records());
+    assert_eq!(
+        groups,
+        [DuplicateGroup {
+            passport_id: "087499704".to_string(),
+            records: vec![0, 1],
+            conflicts: vec![
+                Conflict {
+                    key: "hgt".to_string(),
+                    values: vec![(0, Some("74in".to_string())), (1, Some("75in".to_string()))],
+                },
+                Conflict {
+                    key: "cid".to_string(),
+                    values: vec![(0, None), (1, Some("1".to_string()))],
+                },
+            ],
+        }]
+    );
+}
+
+#[test]
+fn test_find_merge_candidates() {
+    let records = example_records();
+    let candidates = find_merge_candidates(&records, &DedupOptions::default());
+    let pairs: Vec<(usize, usize)> = candidates.iter().map(|c| c.records).collect();
+    // record 3 has another birth year, record 4's id is too different
+    assert_eq!(pairs, [(0, 1), (0, 2), (1, 2)]);
+    assert_eq!(candidates[0].pid_distance, 0);
+    assert_eq!(candidates[0].conflicting_keys, ["hgt"]);
+    assert!((candidates[0].confidence - (0.6 + 0.4 * 3.0 / 4.0)).abs() < 1e-9);
+    assert_eq!(candidates[1].pid_distance, 2);
+    assert!(candidates[1].conflicting_keys.is_empty());
+    assert!((candidates[1].confidence - (0.6 * 7.0 / 9.0 + 0.4)).abs() < 1e-9);
+
+    let strict = DedupOptions {
+        max_pid_distance: 0,
+        min_confidence: 0.95,
+    };
+    assert!(find_merge_candidates(&records, &strict).is_empty());
+}
@@ -0,0 +1,150 @@
+use crate::records::Record;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A field of a record that violates a schema, see `Schema::validate`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldError {
+    /// See `Record::index`.
+    pub record: usize,
+    pub first_line: usize,
+    pub last_line: usize,
+    pub key: String,
+    /// `None` if the field is missing.
+    pub value: Option<String>,
+    /// The kind of violated constraint, e.g. `missing` or `integer_range`.
+    pub kind: &'static str,
+    /// The violated constraint, e.g. `integer in 1920..=2002 with 4 digits`.
+    pub constraint: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(record: &Record, key: &str, value: Option<&str>) -> FieldError {
+        FieldError {
+            record: record.index,
+            first_line: *record.lines.start(),
+            last_line: *record.lines.end(),
+            key: key.to_string(),
+            value: value.map(|value| value.to_string()),
+            kind: "missing",
+            constraint: "required".to_string(),
+            message: "missing".to_string(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "record {} (lines {}-{}): {}",
+            self.record, self.first_line, self.last_line, self.key
+        )?;
+        if let Some(value) = &self.value {
+            write!(f, " {:?}", value)?;
+        }
+        write!(f, " violates {}: {}", self.constraint, self.message)
+    }
+}
+
+/// Counts of records and failures over a whole input.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct ValidationSummary {
+    pub records: usize,
+    pub valid: usize,
+    /// Failures per `key/kind`, e.g. `byr/integer_range`.
+    pub failures: BTreeMap<String, usize>,
+}
+
+impl ValidationSummary {
+    /// Adds a record with its errors.
+    pub fn add(&mut self, errors: &[FieldError]) {
+        self.records += 1;
+        if errors.is_empty() {
+            self.valid += 1;
+        }
+        for error in errors {
+            *self
+                .failures
+                .entry(format!("{}/{}", error.key, error.kind))
+                .or_default() += 1;
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{} of {} records are valid.\n", self.valid, self.records);
+        for (failure, count) in &self.failures {
+            text.push_str(&format!("{}: {}\n", failure, count));
+        }
+        text
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+#[cfg(test)]
+fn example_records() -> Vec<Record> {
+    crate::records::read_records(
+        "eyr:1972 cid:100\nhcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926\n\niyr:2019\nhcl:#602927 eyr:1967 hgt:170cm\necl:grn pid:012533040 byr:1946\n\npid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980\nhcl:#623a2f\n",
+        &crate::records::ReaderOptions::default(),
+    )
+    .unwrap()
+    .0
+}
+
+#[test]
+fn test_validate_collects_all_errors() {
+    let schema = crate::schema::Schema::part2();
+    let records = example_records();
+    let errors = schema.validate(&records[0]);
+    let keys: Vec<&str> = errors.iter().map(|error| error.key.as_str()).collect();
+    assert_eq!(keys, ["eyr", "hgt", "pid"]);
+    assert_eq!(
+        errors[0].to_string(),
+        "record 0 (lines 1-2): eyr \"1972\" violates integer in 2020..=2030 with 4 digits: 1972 is not in 2020..=2030"
+    );
+    assert_eq!(errors[1].kind, "number_with_unit");
+    assert_eq!(
+        errors[2].to_json(),
+        r#"{"record":0,"first_line":1,"last_line":2,"key":"pid","value":"186cm","kind":"digits","constraint":"9 digits","message":"\"186cm\" is not a 9 digit number"}"#
+    );
+    assert!(schema.validate(&records[2]).is_empty());
+
+    let records = crate::records::read_records("\n\ncid:1\n", &Default::default())
+        .unwrap()
+        .0;
+    let errors = schema.validate(&records[0]);
+    assert_eq!(errors.len(), 7);
+    assert_eq!(
+        errors[0].to_string(),
+        "record 0 (lines 3-3): byr violates required: missing"
+    );
+}
+
+#[test]
+fn test_summary() {
+    let schema = crate::schema::Schema::part2();
+    let mut summary = ValidationSummary::default();
+    for record in example_records() {
+        summary.add(&schema.validate(&record));
+    }
+    assert_eq!(summary.records, 3);
+    assert_eq!(summary.valid, 1);
+    assert_eq!(summary.failures["eyr/integer_range"], 2);
+    assert_eq!(
+        summary.to_text(),
+        "1 of 3 records are valid.\neyr/integer_range: 2\nhgt/number_with_unit: 1\npid/digits: 1\n"
+    );
+    assert_eq!(
+        summary.to_json(),
+        r#"{"records":3,"valid":1,"failures":{"eyr/integer_range":2,"hgt/number_with_unit":1,"pid/digits":1}}"#
+    );
+}
@@ -0,0 +1,429 @@
+use crate::records::{read_records, ReaderOptions, Record, RecordsError};
+use crate::{Passport, KNOWN_KEYS};
+use serde::de::{MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Batch formats passports can be read from and written to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// `key:value` fields separated by whitespace, records separated by empty lines.
+    /// Written with one record per line, in field order.
+    Native,
+    /// One JSON object per line, with string values in field order.
+    JsonLines,
+    /// A header row with all keys, known keys first, and one row per record.
+    /// Empty cells are missing fields.
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "native" => Ok(Format::Native),
+            "json" | "jsonl" => Ok(Format::JsonLines),
+            "csv" => Ok(Format::Csv),
+            _ => Err(format!("unknown format {:?}, use native, json or csv", s)),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum FormatError {
+    Native(RecordsError),
+    Json {
+        line: usize,
+        error: serde_json::Error,
+    },
+    Csv {
+        line: usize,
+        message: String,
+    },
+    /// A field that the format can't represent, e.g. a value with whitespace in the native format.
+    /// `key` is `None` if the whole record can't be represented, like an empty passport in the native format.
+    Unrepresentable {
+        record: usize,
+        key: Option<String>,
+    },
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FormatError::Native(error) => write!(f, "{}", error),
+            FormatError::Json { line, error } => write!(f, "line {}: {}", line, error),
+            FormatError::Csv { line, message } => write!(f, "line {}: {}", line, message),
+            FormatError::Unrepresentable {
+                record,
+                key: Some(key),
+            } => write!(
+                f,
+                "record {}: field {} can't be written in this format",
+                record, key
+            ),
+            FormatError::Unrepresentable { record, key: None } => write!(
+                f,
+                "record {}: an empty passport can't be written in this format",
+                record
+            ),
+        }
+    }
+}
+
+impl Error for FormatError {}
+
+impl Format {
+    /// Native input is read with the default `ReaderOptions`, its warnings are dropped.
+    pub fn read_records(&self, input: &str) -> Result<Vec<Record>, FormatError> {
+        match self {
+            Format::Native => read_records(input, &ReaderOptions::default())
+                .map(|(records, _)| records)
+                .map_err(FormatError::Native),
+            Format::JsonLines => read_json_lines(input),
+            Format::Csv => read_csv(input),
+        }
+    }
+
+    pub fn write(&self, passports: &[&Passport]) -> Result<String, FormatError> {
+        match self {
+            Format::Native => write_native(passports),
+            Format::JsonLines => Ok(passports
+                .iter()
+                .map(|passport| serde_json::to_string(passport).unwrap() + "\n")
+                .collect()),
+            Format::Csv => Ok(write_csv(passports)),
+        }
+    }
+}
+
+impl fmt::Display for Passport {
+    /// The native format on one line, in field order.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fields: Vec<String> = self
+            .keys()
+            .map(|key| format!("{}:{}", key, self.get(key).unwrap()))
+            .collect();
+        write!(f, "{}", fields.join(" "))
+    }
+}
+
+fn write_native(passports: &[&Passport]) -> Result<String, FormatError> {
+    let mut native = String::new();
+    for (record, passport) in passports.iter().enumerate() {
+        // an empty line would separate records instead
+        if passport.keys().next().is_none() {
+            return Err(FormatError::Unrepresentable { record, key: None });
+        }
+        for key in passport.keys() {
+            let value = passport.get(key).unwrap();
+            let is_token = |s: &str| !s.contains(char::is_whitespace);
+            if key.is_empty() || key.contains(':') || !is_token(key) || !is_token(value) {
+                return Err(FormatError::Unrepresentable {
+                    record,
+                    key: Some(key.to_string()),
+                });
+            }
+        }
+        if record > 0 {
+            native.push('\n');
+        }
+        native.push_str(&format!("{}\n", passport));
+    }
+    Ok(native)
+}
+
+impl Serialize for Passport {
+    /// A map of strings, in field order.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.keys.len()))?;
+        for key in self.keys() {
+            map.serialize_entry(key, self.get(key).unwrap())?;
+        }
+        map.end()
+    }
+}
+
+struct PassportVisitor;
+
+impl<'de> Visitor<'de> for PassportVisitor {
+    type Value = Passport;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a map of strings")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Passport, A::Error> {
+        let mut passport = Passport::default();
+        while let Some((key, value)) = map.next_entry::<String, String>()? {
+            if passport.insert(&key, &value).is_some() {
+                return Err(serde::de::Error::custom(format!("duplicate key {}", key)));
+            }
+        }
+        Ok(passport)
+    }
+}
+
+impl<'de> Deserialize<'de> for Passport {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(PassportVisitor)
+    }
+}
+
+fn read_json_lines(input: &str) -> Result<Vec<Record>, FormatError> {
+    let mut records = Vec::new();
+    for (line_id, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let passport = serde_json::from_str(line).map_err(|error| FormatError::Json {
+            line: line_id + 1,
+            error,
+        })?;
+        records.push(Record {
+            index: records.len(),
+            lines: line_id + 1..=line_id + 1,
+            passport,
+        });
+    }
+    Ok(records)
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn write_csv(passports: &[&Passport]) -> String {
+    let mut header: Vec<&str> = KNOWN_KEYS.to_vec();
+    for passport in passports {
+        for key in passport.extra().keys() {
+            if !header.contains(&key.as_str()) {
+                header.push(key);
+            }
+        }
+    }
+    header[KNOWN_KEYS.len()..].sort_unstable();
+
+    let mut csv = header
+        .iter()
+        .map(|key| csv_field(key))
+        .collect::<Vec<String>>()
+        .join(",")
+        + "\n";
+    for passport in passports {
+        let row: Vec<String> = header
+            .iter()
+            .map(|key| csv_field(passport.get(key).unwrap_or("")))
+            .collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Rows of fields with the 1-based line they start on. Quoted fields may contain line breaks.
+fn csv_rows(input: &str) -> Result<Vec<(usize, Vec<String>)>, FormatError> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut line = 1;
+    let mut row_line = 1;
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if field.is_empty() => loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        field.push('"');
+                    }
+                    Some('"') => break,
+                    Some(c) => {
+                        line += (c == '\n') as usize;
+                        field.push(c);
+                    }
+                    None => {
+                        return Err(FormatError::Csv {
+                            line: row_line,
+                            message: "unterminated quoted field".to_string(),
+                        })
+                    }
+                }
+            },
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push((row_line, std::mem::take(&mut row)));
+                line += 1;
+                row_line = line;
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push((row_line, row));
+    }
+    Ok(rows)
+}
+
+fn read_csv(input: &str) -> Result<Vec<Record>, FormatError> {
+    let mut rows = csv_rows(input)?
+        .into_iter()
+        .filter(|(_, row)| row.iter().any(|field| !field.is_empty()));
+    let (header_line, header) = match rows.next() {
+        Some((line, header)) => (line, header),
+        None => return Ok(Vec::new()),
+    };
+    if let Some((column, key)) = header
+        .iter()
+        .enumerate()
+        .find(|(column, key)| header[..*column].contains(key))
+    {
+        return Err(FormatError::Csv {
+            line: header_line,
+            message: format!("duplicate column {:?} (column {})", key, column + 1),
+        });
+    }
+    let mut records = Vec::new();
+    for (line, row) in rows {
+        if row.len() != header.len() {
+            return Err(FormatError::Csv {
+                line,
+                message: format!("{} fields instead of {}", row.len(), header.len()),
+            });
+        }
+        let mut passport = Passport::default();
+        for (key, value) in header.iter().zip(&row) {
+            if !value.is_empty() {
+                passport.insert(key, value);
+            }
+        }
+        records.push(Record {
+            index: records.len(),
+            lines: line..=line,
+            passport,
+        });
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+fn example_passports() -> Vec<Passport> {
+    vec![
+        Passport::from_string(
+            "ecl:gry pid:860033327 eyr:2020 hcl:#fffffd\nbyr:1937 iyr:2017 cid:147 hgt:183cm",
+        ),
+        Passport::from_string("iyr:2013 ecl:amb nat:\"de,at\" pid:028048884"),
+    ]
+}
+
+#[cfg(test)]
+fn round_trip(format: Format, passports: &[Passport]) -> Vec<Passport> {
+    let written = format
+        .write(&passports.iter().collect::<Vec<&Passport>>())
+        .unwrap();
+    let records = format.read_records(&written).unwrap();
+    records.into_iter().map(|record| record.passport).collect()
+}
+
+#[test]
+fn test_native_round_trip() {
+    let passports = example_passports();
+    let written = Format::Native
+        .write(&passports.iter().collect::<Vec<_>>())
+        .unwrap();
+    assert_eq!(
+        written,
+        "ecl:gry pid:860033327 eyr:2020 hcl:#fffffd byr:1937 iyr:2017 cid:147 hgt:183cm\n\niyr:2013 ecl:amb nat:\"de,at\" pid:028048884\n"
+    );
+    assert_eq!(round_trip(Format::Native, &passports), passports);
+
+    let mut spaced = Passport::default();
+    spaced.insert("hgt", "183 cm");
+    assert!(matches!(
+        Format::Native.write(&[&spaced]),
+        Err(FormatError::Unrepresentable { record: 0, .. })
+    ));
+
+    let empty = Passport::default();
+    let error = Format::Native.write(&[&passports[0], &empty]).unwrap_err();
+    assert!(matches!(
+        error,
+        FormatError::Unrepresentable {
+            record: 1,
+            key: None
+        }
+    ));
+    assert_eq!(
+        error.to_string(),
+        "record 1: an empty passport can't be written in this format"
+    );
+}
+
+#[test]
+fn test_json_round_trip() {
+    let passports = example_passports();
+    let written = Format::JsonLines
+        .write(&passports.iter().collect::<Vec<_>>())
+        .unwrap();
+    assert!(
+        written.starts_with(r##"{"ecl":"gry","pid":"860033327","eyr":"2020","hcl":"#fffffd","##)
+    );
+    assert_eq!(round_trip(Format::JsonLines, &passports), passports);
+
+    let error = Format::JsonLines
+        .read_records("{\"byr\":\"1937\"}\n\n{\"byr\":1937}\n")
+        .unwrap_err();
+    assert!(error
+        .to_string()
+        .starts_with("line 3: invalid type: integer"));
+}
+
+#[test]
+fn test_csv() {
+    let passports = example_passports();
+    let written = Format::Csv
+        .write(&passports.iter().collect::<Vec<_>>())
+        .unwrap();
+    assert_eq!(
+        written,
+        "byr,iyr,eyr,hgt,hcl,ecl,pid,cid,nat\n1937,2017,2020,183cm,#fffffd,gry,860033327,147,\n,2013,,,,amb,028048884,,\"\"\"de,at\"\"\"\n"
+    );
+    let read = round_trip(Format::Csv, &passports);
+    for (read, passport) in read.iter().zip(&passports) {
+        for key in passport.keys() {
+            assert_eq!(read.get(key), passport.get(key));
+        }
+        assert_eq!(read.keys().count(), passport.keys().count());
+    }
+
+    let records = Format::Csv
+        .read_records("pid,note\r\n1,\"two\r\nlines\"\r\n\r\n2,\r\n")
+        .unwrap();
+    assert_eq!(records[0].passport.get("note"), Some("two\r\nlines"));
+    assert_eq!(records[1].lines, 5..=5);
+    assert_eq!(records[1].passport.get("note"), None);
+    assert_eq!(
+        Format::Csv
+            .read_records("pid\n1,2\n")
+            .unwrap_err()
+            .to_string(),
+        "line 2: 2 fields instead of 1"
+    );
+    assert_eq!(
+        Format::Csv
+            .read_records("\npid,byr,pid\n1,2,3\n")
+            .unwrap_err()
+            .to_string(),
+        "line 2: duplicate column \"pid\" (column 3)"
+    );
+}
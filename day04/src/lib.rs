@@ -0,0 +1,173 @@
+use schema::Schema;
+use std::collections::BTreeMap;
+
+pub mod dedup;
+pub mod diagnostics;
+pub mod formats;
+pub mod records;
+pub mod schema;
+pub mod validated;
+
+/// The keys of the puzzle, other keys end up in `Passport::extra`.
+pub const KNOWN_KEYS: [&str; 8] = ["byr", "iyr", "eyr", "hgt", "hcl", "ecl", "pid", "cid"];
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Passport {
+    birth_year: Option<String>,
+    issue_year: Option<String>,
+    expiration_year: Option<String>,
+    height: Option<String>,
+    hair_color: Option<String>,
+    eye_color: Option<String>,
+    passport_id: Option<String>,
+    country_id: Option<String>,
+    extra: BTreeMap<String, String>,
+    /// All keys, in the order they were inserted.
+    keys: Vec<String>,
+}
+
+impl Passport {
+    /// Parses whitespace separated `key:value` fields. Of duplicate keys, the first one wins.
+    /// See `records::read_records` for reading whole files with issue reporting.
+    ///
+    /// Panics on fields without a key or `:`.
+    pub fn from_string(passport_string: &str) -> Passport {
+        let mut p = Passport::default();
+        for field in passport_string.split_whitespace() {
+            let (key, value) = records::split_field(field)
+                .unwrap_or_else(|| panic!("malformed field {:?}", field));
+            if p.get(key).is_none() {
+                p.insert(key, value);
+            }
+        }
+        p
+    }
+
+    fn known_field(&mut self, key: &str) -> Option<&mut Option<String>> {
+        match key {
+            "byr" => Some(&mut self.birth_year),
+            "iyr" => Some(&mut self.issue_year),
+            "eyr" => Some(&mut self.expiration_year),
+            "hgt" => Some(&mut self.height),
+            "hcl" => Some(&mut self.hair_color),
+            "ecl" => Some(&mut self.eye_color),
+            "pid" => Some(&mut self.passport_id),
+            "cid" => Some(&mut self.country_id),
+            _ => None,
+        }
+    }
+
+    /// Sets the field and returns its previous value. Replaced fields keep their position in `keys`.
+    pub fn insert(&mut self, key: &str, value: &str) -> Option<String> {
+        let previous = match self.known_field(key) {
+            Some(field) => field.replace(value.to_string()),
+            None => self.extra.insert(key.to_string(), value.to_string()),
+        };
+        if previous.is_none() {
+            self.keys.push(key.to_string());
+        }
+        previous
+    }
+
+    /// The value of the field with the given key, e.g. `byr`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        let value = match key {
+            "byr" => &self.birth_year,
+            "iyr" => &self.issue_year,
+            "eyr" => &self.expiration_year,
+            "hgt" => &self.height,
+            "hcl" => &self.hair_color,
+            "ecl" => &self.eye_color,
+            "pid" => &self.passport_id,
+            "cid" => &self.country_id,
+            _ => return self.extra.get(key).map(|value| value.as_str()),
+        };
+        value.as_deref()
+    }
+
+    /// All keys, in input order.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.keys.iter().map(|key| key.as_str())
+    }
+
+    /// Fields whose keys aren't in `KNOWN_KEYS`.
+    pub fn extra(&self) -> &BTreeMap<String, String> {
+        &self.extra
+    }
+
+    pub fn is_valid_part1(&self) -> bool {
+        Schema::part1().is_valid(self)
+    }
+
+    pub fn is_valid_part2(&self) -> bool {
+        Schema::part2().is_valid(self)
+    }
+}
+
+#[test]
+fn test_part2_invalid_examples() {
+    let invalid_passports_string = "eyr:1972 cid:100\nhcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926\n\niyr:2019\nhcl:#602927 eyr:1967 hgt:170cm\necl:grn pid:012533040 byr:1946\n\nhcl:dab227 iyr:2012\necl:brn hgt:182cm pid:021572410 eyr:2020 byr:1992 cid:277\n\nhgt:59cm ecl:zzz\neyr:2038 hcl:74454a iyr:2023\npid:3556412378 byr:2007";
+
+    for invalid_passport_string in invalid_passports_string.split("\n\n") {
+        let invalid_passport = Passport::from_string(invalid_passport_string);
+        println!("Checking invalid passport {:?}", invalid_passport);
+        assert!(!invalid_passport.is_valid_part2());
+    }
+}
+
+#[test]
+fn test_part2_valid_examples() {
+    let valid_passports_string = "pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980\nhcl:#623a2f\n\neyr:2029 ecl:blu cid:129 byr:1989\niyr:2014 pid:896056539 hcl:#a97842 hgt:165cm\n\nhcl:#888785\nhgt:164cm byr:2001 iyr:2015 cid:88\npid:545766238 ecl:hzl\neyr:2022\n\niyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719";
+
+    for valid_passport_string in valid_passports_string.split("\n\n") {
+        let valid_passport = Passport::from_string(valid_passport_string);
+        println!("Checking valid passport {:?}", valid_passport);
+        assert!(valid_passport.is_valid_part2());
+    }
+}
+
+#[test]
+fn test_from_string_example() {
+    let passport_string =
+        "ecl:gry pid:860033327 eyr:2020 hcl:#fffffd\nbyr:1937 iyr:2017 cid:147 hgt:183cm";
+    let passport = Passport::from_string(passport_string);
+    assert_eq!(passport.eye_color, Some("gry".to_string()));
+    assert_eq!(passport.passport_id, Some("860033327".to_string()));
+    assert_eq!(passport.expiration_year, Some("2020".to_string()));
+    assert_eq!(passport.hair_color, Some("#fffffd".to_string()));
+    assert_eq!(passport.birth_year, Some("1937".to_string()));
+    assert_eq!(passport.issue_year, Some("2017".to_string()));
+    assert_eq!(passport.country_id, Some("147".to_string()));
+    assert_eq!(passport.height, Some("183cm".to_string()));
+}
+
+#[test]
+fn test_example() {
+    let mut nr_valid = 0;
+    let input_string = "ecl:gry pid:860033327 eyr:2020 hcl:#fffffd\nbyr:1937 iyr:2017 cid:147 hgt:183cm\n\niyr:2013 ecl:amb cid:350 eyr:2023 pid:028048884\nhcl:#cfa07d byr:1929\n\nhcl:#ae17e1 iyr:2013\neyr:2024\necl:brn pid:760753108 byr:1931\nhgt:179cm\n\nhcl:#cfa07d eyr:2025 pid:166559648\niyr:2011 ecl:brn hgt:59in";
+    for passport_string in input_string.split("\n\n") {
+        if Passport::from_string(passport_string).is_valid_part1() {
+            nr_valid += 1;
+        }
+    }
+    assert_eq!(nr_valid, 2);
+}
+
+#[test]
+fn test_from_string_keeps_unknown_keys_and_order() {
+    let passport = Passport::from_string("pid:1 nat:de byr:1937 pid:2 url:a:b");
+    assert_eq!(passport.get("pid"), Some("1"));
+    assert_eq!(passport.get("nat"), Some("de"));
+    assert_eq!(passport.get("url"), Some("a:b"));
+    assert_eq!(
+        passport.keys().collect::<Vec<&str>>(),
+        ["pid", "nat", "byr", "url"]
+    );
+    assert_eq!(passport.extra().len(), 2);
+}
+
+#[test]
+#[should_panic(expected = "malformed field \"byr\"")]
+fn test_from_string_malformed() {
+    Passport::from_string("pid:1 byr");
+}
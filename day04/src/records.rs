@@ -0,0 +1,240 @@
+use crate::{Passport, KNOWN_KEYS};
+use std::error::Error;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// A passport and where it is in the input.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    /// 0-based position among the records of the input.
+    pub index: usize,
+    /// 1-based line numbers of the first and last line.
+    pub lines: RangeInclusive<usize>,
+    pub passport: Passport,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IssueLevel {
+    Ignore,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReaderOptions {
+    /// Of duplicate keys, the first one is kept.
+    pub duplicate_keys: IssueLevel,
+    /// Keys not in `KNOWN_KEYS`, they are kept in `Passport::extra`.
+    pub unknown_keys: IssueLevel,
+}
+
+impl Default for ReaderOptions {
+    fn default() -> Self {
+        ReaderOptions {
+            duplicate_keys: IssueLevel::Warning,
+            unknown_keys: IssueLevel::Warning,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IssueKind {
+    /// `value` is the ignored value.
+    DuplicateKey {
+        key: String,
+        value: String,
+    },
+    UnknownKey {
+        key: String,
+    },
+    /// A field without a key or `:`. Always an error.
+    MalformedField {
+        field: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    /// `Warning` or `Error`.
+    pub level: IssueLevel,
+    /// See `Record::index`.
+    pub record: usize,
+    /// 1-based.
+    pub line: usize,
+    pub kind: IssueKind,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let level = match self.level {
+            IssueLevel::Error => "error",
+            _ => "warning",
+        };
+        write!(f, "line {}: {}: ", self.line, level)?;
+        match &self.kind {
+            IssueKind::DuplicateKey { key, value } => {
+                write!(f, "duplicate key {} (ignored value {:?})", key, value)
+            }
+            IssueKind::UnknownKey { key } => write!(f, "unknown key {}", key),
+            IssueKind::MalformedField { field } => write!(f, "malformed field {:?}", field),
+        }
+    }
+}
+
+/// All issues of an input with at least one error.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordsError {
+    pub issues: Vec<Issue>,
+}
+
+impl fmt::Display for RecordsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let errors: Vec<String> = self
+            .issues
+            .iter()
+            .filter(|issue| issue.level == IssueLevel::Error)
+            .map(|issue| issue.to_string())
+            .collect();
+        write!(f, "{}", errors.join("\n"))
+    }
+}
+
+impl Error for RecordsError {}
+
+/// Splits `key:value`, the value may contain more `:`.
+pub fn split_field(field: &str) -> Option<(&str, &str)> {
+    field.split_once(':').filter(|(key, _)| !key.is_empty())
+}
+
+/// Splits the input into records at lines that are empty or only contain whitespace.
+/// Handles `\n` and `\r\n` line endings.
+///
+/// Returns the records and warnings, or all issues if any of them is an error.
+pub fn read_records(
+    input: &str,
+    options: &ReaderOptions,
+) -> Result<(Vec<Record>, Vec<Issue>), RecordsError> {
+    let mut records: Vec<Record> = Vec::new();
+    let mut issues = Vec::new();
+    let mut current: Option<Record> = None;
+
+    for (line_id, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            records.extend(current.take());
+            continue;
+        }
+        let record = current.get_or_insert_with(|| Record {
+            index: records.len(),
+            lines: line_id + 1..=line_id + 1,
+            passport: Passport::default(),
+        });
+        record.lines = *record.lines.start()..=line_id + 1;
+
+        let record_index = record.index;
+        let mut report = |level: IssueLevel, kind: IssueKind| {
+            if level != IssueLevel::Ignore {
+                issues.push(Issue {
+                    level,
+                    record: record_index,
+                    line: line_id + 1,
+                    kind,
+                });
+            }
+        };
+        for field in line.split_whitespace() {
+            let (key, value) = match split_field(field) {
+                Some(key_value) => key_value,
+                None => {
+                    let field = field.to_string();
+                    report(IssueLevel::Error, IssueKind::MalformedField { field });
+                    continue;
+                }
+            };
+            if record.passport.get(key).is_some() {
+                let kind = IssueKind::DuplicateKey {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                };
+                report(options.duplicate_keys, kind);
+                continue;
+            }
+            if !KNOWN_KEYS.contains(&key) {
+                let kind = IssueKind::UnknownKey {
+                    key: key.to_string(),
+                };
+                report(options.unknown_keys, kind);
+            }
+            record.passport.insert(key, value);
+        }
+    }
+    records.extend(current);
+
+    match issues.iter().any(|issue| issue.level == IssueLevel::Error) {
+        true => Err(RecordsError { issues }),
+        false => Ok((records, issues)),
+    }
+}
+
+#[test]
+fn test_read_records() {
+    let (records, issues) = read_records(
+        "byr:1937\niyr:2017\n\n\ncid:147\n\necl:gry",
+        &ReaderOptions::default(),
+    )
+    .unwrap();
+    let lines: Vec<RangeInclusive<usize>> = records.iter().map(|r| r.lines.clone()).collect();
+    assert_eq!(lines, [1..=2, 5..=5, 7..=7]);
+    assert_eq!(records[2].index, 2);
+    assert_eq!(records[0].passport.get("iyr"), Some("2017"));
+    assert!(issues.is_empty());
+    assert_eq!(
+        read_records("", &ReaderOptions::default()),
+        Ok((vec![], vec![]))
+    );
+}
+
+#[test]
+fn test_read_records_crlf_and_whitespace_separators() {
+    let input = "byr:1937\r\niyr:2017 \r\n \t \r\ncid:147\r\n  \necl:gry\r\n";
+    let (records, _) = read_records(input, &ReaderOptions::default()).unwrap();
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[0].passport.get("iyr"), Some("2017"));
+    assert_eq!(records[1].lines, 4..=4);
+    assert_eq!(records[2].passport.get("ecl"), Some("gry"));
+}
+
+#[test]
+fn test_read_records_issues() {
+    let input = "byr:1937 nat:de\nbyr:1940\n\npid:1";
+    let (records, issues) = read_records(input, &ReaderOptions::default()).unwrap();
+    assert_eq!(records[0].passport.get("byr"), Some("1937"));
+    assert_eq!(records[0].passport.extra()["nat"], "de");
+    assert_eq!(
+        issues
+            .iter()
+            .map(|i| i.to_string())
+            .collect::<Vec<String>>(),
+        [
+            "line 1: warning: unknown key nat",
+            "line 2: warning: duplicate key byr (ignored value \"1940\")"
+        ]
+    );
+
+    let strict = ReaderOptions {
+        duplicate_keys: IssueLevel::Error,
+        unknown_keys: IssueLevel::Ignore,
+    };
+    let error = read_records(input, &strict).unwrap_err();
+    assert_eq!(error.issues.len(), 1);
+    assert_eq!(
+        error.to_string(),
+        "line 2: error: duplicate key byr (ignored value \"1940\")"
+    );
+
+    let error = read_records("byr:1937\n\npid\n:1", &ReaderOptions::default()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "line 3: error: malformed field \"pid\"\nline 4: error: malformed field \":1\""
+    );
+    assert_eq!(error.issues[1].record, 1);
+}
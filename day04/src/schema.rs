@@ -0,0 +1,369 @@
+use crate::diagnostics::FieldError;
+use crate::records::Record;
+use crate::Passport;
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::sync::OnceLock;
+
+/// Which fields a passport needs and what their values have to look like.
+///
+/// Schemas are usually loaded from TOML or JSON, e.g. `schemas/part2.toml`:
+///
+/// ```toml
+/// [[fields]]
+/// key = "byr"
+/// required = true
+/// constraints = [{ type = "integer_range", min = 1920, max = 2002, digits = 4 }]
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Schema {
+    pub fields: Vec<FieldRule>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FieldRule {
+    pub key: String,
+    #[serde(default)]
+    pub required: bool,
+    /// All of them have to hold if the field is present.
+    #[serde(default)]
+    pub constraints: Vec<Constraint>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Bounds {
+    pub min: i64,
+    pub max: i64,
+}
+
+impl Bounds {
+    fn check(&self, value: i64) -> Result<(), String> {
+        match (self.min..=self.max).contains(&value) {
+            true => Ok(()),
+            false => Err(format!("{} is not in {}..={}", value, self.min, self.max)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Constraint {
+    /// A decimal integer within the bounds, optionally with an exact number of digits.
+    IntegerRange {
+        min: i64,
+        max: i64,
+        digits: Option<usize>,
+    },
+    /// A decimal integer followed by one of the units, e.g. `183cm`, with bounds per unit.
+    NumberWithUnit {
+        units: BTreeMap<String, Bounds>,
+    },
+    /// `#` followed by six lowercase hex digits.
+    HexColor,
+    OneOf {
+        values: Vec<String>,
+    },
+    /// Exactly `length` decimal digits, leading zeroes included.
+    Digits {
+        length: usize,
+    },
+    /// Has to match the whole value.
+    Regex {
+        pattern: Pattern,
+    },
+}
+
+/// A regex that has to match the whole value.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    regex: Regex,
+    source: String,
+}
+
+impl Pattern {
+    pub fn new(pattern: &str) -> Result<Pattern, regex::Error> {
+        Ok(Pattern {
+            regex: Regex::new(&format!("^(?:{})$", pattern))?,
+            source: pattern.to_string(),
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        Pattern::new(&pattern).map_err(serde::de::Error::custom)
+    }
+}
+
+fn is_digits(value: &str) -> bool {
+    !value.is_empty() && value.chars().all(|c| c.is_ascii_digit())
+}
+
+fn parse_integer(value: &str) -> Result<i64, String> {
+    match is_digits(value) {
+        true => value.parse().map_err(|_| format!("{} is too large", value)),
+        false => Err(format!("{:?} is not a number", value)),
+    }
+}
+
+impl Constraint {
+    pub fn check(&self, value: &str) -> Result<(), String> {
+        match self {
+            Constraint::IntegerRange { min, max, digits } => {
+                let number = parse_integer(value)?;
+                if let Some(digits) = digits {
+                    if value.len() != *digits {
+                        return Err(format!("{:?} doesn't have {} digits", value, digits));
+                    }
+                }
+                Bounds {
+                    min: *min,
+                    max: *max,
+                }
+                .check(number)
+            }
+            Constraint::NumberWithUnit { units } => {
+                // the longest unit, so that e.g. "cm" wins over "m"
+                let (unit, bounds) = units
+                    .iter()
+                    .filter(|(unit, _)| value.ends_with(unit.as_str()))
+                    .max_by_key(|(unit, _)| unit.len())
+                    .ok_or_else(|| {
+                        let units: Vec<&str> = units.keys().map(|unit| unit.as_str()).collect();
+                        format!("{:?} doesn't end with one of {:?}", value, units)
+                    })?;
+                bounds.check(parse_integer(&value[..value.len() - unit.len()])?)
+            }
+            Constraint::HexColor => {
+                let is_color = value.len() == 7
+                    && value.starts_with('#')
+                    && value[1..]
+                        .chars()
+                        .all(|c| matches!(c, '0'..='9' | 'a'..='f'));
+                match is_color {
+                    true => Ok(()),
+                    false => Err(format!("{:?} is not a hex color like #12ab3f", value)),
+                }
+            }
+            Constraint::OneOf { values } => match values.iter().any(|known| known == value) {
+                true => Ok(()),
+                false => Err(format!("{:?} is not one of {:?}", value, values)),
+            },
+            Constraint::Digits { length } => match value.len() == *length && is_digits(value) {
+                true => Ok(()),
+                false => Err(format!("{:?} is not a {} digit number", value, length)),
+            },
+            Constraint::Regex { pattern } => match pattern.regex.is_match(value) {
+                true => Ok(()),
+                false => Err(format!("{:?} doesn't match {}", value, pattern.source)),
+            },
+        }
+    }
+
+    /// The constraint's `type` in schema files.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Constraint::IntegerRange { .. } => "integer_range",
+            Constraint::NumberWithUnit { .. } => "number_with_unit",
+            Constraint::HexColor => "hex_color",
+            Constraint::OneOf { .. } => "one_of",
+            Constraint::Digits { .. } => "digits",
+            Constraint::Regex { .. } => "regex",
+        }
+    }
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Constraint::IntegerRange { min, max, digits } => {
+                write!(f, "integer in {}..={}", min, max)?;
+                match digits {
+                    Some(digits) => write!(f, " with {} digits", digits),
+                    None => Ok(()),
+                }
+            }
+            Constraint::NumberWithUnit { units } => {
+                let units: Vec<String> = units
+                    .iter()
+                    .map(|(unit, bounds)| format!("{} in {}..={}", unit, bounds.min, bounds.max))
+                    .collect();
+                write!(f, "number with unit, {}", units.join(" or "))
+            }
+            Constraint::HexColor => write!(f, "hex color"),
+            Constraint::OneOf { values } => write!(f, "one of {}", values.join(", ")),
+            Constraint::Digits { length } => write!(f, "{} digits", length),
+            Constraint::Regex { pattern } => write!(f, "regex {}", pattern.source),
+        }
+    }
+}
+
+impl FieldRule {
+    pub fn check(&self, value: Option<&str>) -> Result<(), String> {
+        match value {
+            None if self.required => Err("missing".to_string()),
+            None => Ok(()),
+            Some(value) => self
+                .constraints
+                .iter()
+                .try_for_each(|constraint| constraint.check(value)),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SchemaError {
+    Toml(toml::de::Error),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SchemaError::Toml(error) => write!(f, "invalid TOML schema: {}", error),
+            SchemaError::Json(error) => write!(f, "invalid JSON schema: {}", error),
+        }
+    }
+}
+
+impl Error for SchemaError {}
+
+impl Schema {
+    pub fn from_toml(schema: &str) -> Result<Schema, SchemaError> {
+        toml::from_str(schema).map_err(SchemaError::Toml)
+    }
+
+    pub fn from_json(schema: &str) -> Result<Schema, SchemaError> {
+        serde_json::from_str(schema).map_err(SchemaError::Json)
+    }
+
+    /// The rules of part 1, from `schemas/part1.toml`.
+    pub fn part1() -> &'static Schema {
+        static PART1: OnceLock<Schema> = OnceLock::new();
+        PART1.get_or_init(|| Schema::from_toml(include_str!("../schemas/part1.toml")).unwrap())
+    }
+
+    /// The rules of part 2, from `schemas/part2.toml`.
+    pub fn part2() -> &'static Schema {
+        static PART2: OnceLock<Schema> = OnceLock::new();
+        PART2.get_or_init(|| Schema::from_toml(include_str!("../schemas/part2.toml")).unwrap())
+    }
+
+    pub fn is_valid(&self, passport: &Passport) -> bool {
+        self.fields
+            .iter()
+            .all(|rule| rule.check(passport.get(&rule.key)).is_ok())
+    }
+
+    /// All fields of the record that violate the schema, in schema order.
+    /// Each field is only reported for the first constraint it violates.
+    pub fn validate(&self, record: &Record) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        for rule in &self.fields {
+            let value = record.passport.get(&rule.key);
+            let mut error = FieldError::new(record, &rule.key, value);
+            match value {
+                None if rule.required => errors.push(error),
+                None => {}
+                Some(value) => {
+                    for constraint in &rule.constraints {
+                        if let Err(message) = constraint.check(value) {
+                            error.kind = constraint.kind();
+                            error.constraint = constraint.to_string();
+                            error.message = message;
+                            errors.push(error);
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        errors
+    }
+}
+
+#[test]
+fn test_constraints() {
+    let year = Constraint::IntegerRange {
+        min: 1920,
+        max: 2002,
+        digits: Some(4),
+    };
+    assert_eq!(year.check("2002"), Ok(()));
+    assert_eq!(
+        year.check("2003"),
+        Err("2003 is not in 1920..=2002".to_string())
+    );
+    assert!(year.check("01990").is_err());
+    assert!(year.check("+1990").is_err());
+
+    let schema = Schema::part2();
+    let height = &schema.fields[3];
+    assert_eq!(height.check(Some("60in")), Ok(()));
+    assert_eq!(height.check(Some("190cm")), Ok(()));
+    assert!(height.check(Some("190in")).is_err());
+    assert_eq!(
+        height.check(Some("190")),
+        Err("\"190\" doesn't end with one of [\"cm\", \"in\"]".to_string())
+    );
+    assert_eq!(height.check(None), Err("missing".to_string()));
+
+    assert_eq!(Constraint::HexColor.check("#123abc"), Ok(()));
+    assert!(Constraint::HexColor.check("#123abz").is_err());
+    assert!(Constraint::HexColor.check("123abc").is_err());
+    assert_eq!(Constraint::Digits { length: 9 }.check("000000001"), Ok(()));
+    assert!(Constraint::Digits { length: 9 }
+        .check("0123456789")
+        .is_err());
+
+    let regex = Constraint::Regex {
+        pattern: Pattern::new("[a-z]+[0-9]").unwrap(),
+    };
+    assert_eq!(regex.check("abc1"), Ok(()));
+    assert!(regex.check("abc12").is_err());
+}
+
+#[test]
+fn test_schema_from_json_and_toml() {
+    let json = r#"{"fields": [
+        {"key": "cid", "required": true, "constraints": [{"type": "regex", "pattern": "[0-9]{2,3}"}]},
+        {"key": "ecl", "constraints": [{"type": "one_of", "values": ["amb", "blu"]}]}
+    ]}"#;
+    let schema = Schema::from_json(json).unwrap();
+    let passport = Passport::from_string("cid:147 ecl:amb");
+    assert!(schema.is_valid(&passport));
+    assert!(schema.is_valid(&Passport::from_string("cid:99")));
+    assert!(!schema.is_valid(&Passport::from_string("cid:1 ecl:amb")));
+    assert!(!schema.is_valid(&Passport::from_string("ecl:amb")));
+    assert!(!schema.is_valid(&Passport::from_string("cid:147 ecl:gry")));
+
+    assert!(Schema::from_toml("[[fields]]\nkey = \"byr\"\nrequird = true\n").is_err());
+    let error = Schema::from_json(
+        r#"{"fields": [{"key": "cid", "constraints": [{"type": "regex", "pattern": "("}]}]}"#,
+    )
+    .unwrap_err();
+    assert!(error.to_string().starts_with("invalid JSON schema: "));
+}
+
+#[test]
+fn test_schema_with_overlapping_units() {
+    let toml = r#"
+        [[fields]]
+        key = "hgt"
+        required = true
+        constraints = [{ type = "number_with_unit", units = { m = { min = 1, max = 2 }, cm = { min = 150, max = 193 }, mm = { min = 1500, max = 1930 } } }]
+    "#;
+    let schema = Schema::from_toml(toml).unwrap();
+    assert!(schema.is_valid(&Passport::from_string("hgt:183cm")));
+    assert!(schema.is_valid(&Passport::from_string("hgt:1830mm")));
+    assert!(schema.is_valid(&Passport::from_string("hgt:2m")));
+    assert!(!schema.is_valid(&Passport::from_string("hgt:183m")));
+    assert!(!schema.is_valid(&Passport::from_string("hgt:2cm")));
+}
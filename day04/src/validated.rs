@@ -0,0 +1,364 @@
+use crate::Passport;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+/// A four digit year.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Year(pub u16);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeightUnit {
+    Cm,
+    In,
+}
+
+/// Within 150 to 193 cm or 59 to 76 in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Height {
+    pub value: u16,
+    pub unit: HeightUnit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HexColor(pub [u8; 3]);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EyeColor {
+    Amb,
+    Blu,
+    Brn,
+    Gry,
+    Grn,
+    Hzl,
+    Oth,
+}
+
+/// Nine decimal digits, leading zeroes included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PassportId(pub [u8; 9]);
+
+/// Not checked, any value is fine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CountryId(pub String);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidatedPassport {
+    pub birth_year: Year,
+    pub issue_year: Year,
+    pub expiration_year: Year,
+    pub height: Height,
+    pub hair_color: HexColor,
+    pub eye_color: EyeColor,
+    pub passport_id: PassportId,
+    pub country_id: Option<CountryId>,
+}
+
+/// Why a field couldn't be turned into its domain type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidField {
+    pub key: &'static str,
+    /// `None` if the field is missing.
+    pub value: Option<String>,
+    pub reason: String,
+}
+
+impl fmt::Display for InvalidField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.value {
+            Some(value) => write!(f, "{}: {:?}: {}", self.key, value, self.reason),
+            None => write!(f, "{}: {}", self.key, self.reason),
+        }
+    }
+}
+
+impl FromStr for Year {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.len() == 4 && s.chars().all(|c| c.is_ascii_digit()) {
+            true => Ok(Year(s.parse().unwrap())),
+            false => Err("not a four digit year".to_string()),
+        }
+    }
+}
+
+impl Year {
+    fn check(self, min: u16, max: u16) -> Result<Year, String> {
+        match (min..=max).contains(&self.0) {
+            true => Ok(self),
+            false => Err(format!("not within {} and {}", min, max)),
+        }
+    }
+}
+
+impl FromStr for Height {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (number, unit, min, max) = if let Some(number) = s.strip_suffix("cm") {
+            (number, HeightUnit::Cm, 150, 193)
+        } else if let Some(number) = s.strip_suffix("in") {
+            (number, HeightUnit::In, 59, 76)
+        } else {
+            return Err("not in cm or in".to_string());
+        };
+        if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+            return Err("not a number followed by a unit".to_string());
+        }
+        match number.parse() {
+            Ok(value) if (min..=max).contains(&value) => Ok(Height { value, unit }),
+            _ => Err(format!("not within {} and {} {}", min, max, unit)),
+        }
+    }
+}
+
+impl FromStr for HexColor {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s
+            .strip_prefix('#')
+            .filter(|digits| digits.len() == 6)
+            .filter(|digits| digits.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f')))
+            .ok_or_else(|| "not a hex color like #12ab3f".to_string())?;
+        let channel = |id: usize| u8::from_str_radix(&digits[2 * id..2 * id + 2], 16).unwrap();
+        Ok(HexColor([channel(0), channel(1), channel(2)]))
+    }
+}
+
+impl FromStr for EyeColor {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "amb" => Ok(EyeColor::Amb),
+            "blu" => Ok(EyeColor::Blu),
+            "brn" => Ok(EyeColor::Brn),
+            "gry" => Ok(EyeColor::Gry),
+            "grn" => Ok(EyeColor::Grn),
+            "hzl" => Ok(EyeColor::Hzl),
+            "oth" => Ok(EyeColor::Oth),
+            _ => Err("not one of amb blu brn gry grn hzl oth".to_string()),
+        }
+    }
+}
+
+impl FromStr for PassportId {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 9 || !s.chars().all(|c| c.is_ascii_digit()) {
+            return Err("not a nine digit number".to_string());
+        }
+        let mut digits = [0; 9];
+        for (digit, c) in digits.iter_mut().zip(s.bytes()) {
+            *digit = c - b'0';
+        }
+        Ok(PassportId(digits))
+    }
+}
+
+impl fmt::Display for Year {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}", self.0)
+    }
+}
+
+impl fmt::Display for HeightUnit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HeightUnit::Cm => write!(f, "cm"),
+            HeightUnit::In => write!(f, "in"),
+        }
+    }
+}
+
+impl fmt::Display for Height {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.value, self.unit)
+    }
+}
+
+impl fmt::Display for HexColor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let [r, g, b] = self.0;
+        write!(f, "#{:02x}{:02x}{:02x}", r, g, b)
+    }
+}
+
+impl fmt::Display for EyeColor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            EyeColor::Amb => "amb",
+            EyeColor::Blu => "blu",
+            EyeColor::Brn => "brn",
+            EyeColor::Gry => "gry",
+            EyeColor::Grn => "grn",
+            EyeColor::Hzl => "hzl",
+            EyeColor::Oth => "oth",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for PassportId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for digit in &self.0 {
+            write!(f, "{}", digit)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for CountryId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Parses one field, collecting the error instead of returning early.
+fn field<T>(
+    passport: &Passport,
+    key: &'static str,
+    parse: impl FnOnce(&str) -> Result<T, String>,
+    errors: &mut Vec<InvalidField>,
+) -> Option<T> {
+    let value = passport.get(key);
+    let result = match value {
+        Some(value) => parse(value),
+        None => Err("missing".to_string()),
+    };
+    result
+        .map_err(|reason| {
+            errors.push(InvalidField {
+                key,
+                value: value.map(|value| value.to_string()),
+                reason,
+            })
+        })
+        .ok()
+}
+
+impl TryFrom<&Passport> for ValidatedPassport {
+    type Error = Vec<InvalidField>;
+
+    /// Checks the part 2 rules and returns the errors of all invalid fields.
+    fn try_from(passport: &Passport) -> Result<Self, Self::Error> {
+        let mut errors = Vec::new();
+        let year = |min, max| move |value: &str| value.parse::<Year>()?.check(min, max);
+        let birth_year = field(passport, "byr", year(1920, 2002), &mut errors);
+        let issue_year = field(passport, "iyr", year(2010, 2020), &mut errors);
+        let expiration_year = field(passport, "eyr", year(2020, 2030), &mut errors);
+        let height = field(passport, "hgt", str::parse, &mut errors);
+        let hair_color = field(passport, "hcl", str::parse, &mut errors);
+        let eye_color = field(passport, "ecl", str::parse, &mut errors);
+        let passport_id = field(passport, "pid", str::parse, &mut errors);
+
+        match (
+            birth_year,
+            issue_year,
+            expiration_year,
+            height,
+            hair_color,
+            eye_color,
+            passport_id,
+        ) {
+            (
+                Some(birth_year),
+                Some(issue_year),
+                Some(expiration_year),
+                Some(height),
+                Some(hair_color),
+                Some(eye_color),
+                Some(passport_id),
+            ) => Ok(ValidatedPassport {
+                birth_year,
+                issue_year,
+                expiration_year,
+                height,
+                hair_color,
+                eye_color,
+                passport_id,
+                country_id: passport.get("cid").map(|cid| CountryId(cid.to_string())),
+            }),
+            _ => Err(errors),
+        }
+    }
+}
+
+#[test]
+fn test_validated_passport() {
+    let passport = Passport::from_string(
+        "pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980\nhcl:#623a2f",
+    );
+    let validated = ValidatedPassport::try_from(&passport).unwrap();
+    assert_eq!(
+        validated,
+        ValidatedPassport {
+            birth_year: Year(1980),
+            issue_year: Year(2012),
+            expiration_year: Year(2030),
+            height: Height {
+                value: 74,
+                unit: HeightUnit::In
+            },
+            hair_color: HexColor([0x62, 0x3a, 0x2f]),
+            eye_color: EyeColor::Grn,
+            passport_id: PassportId([0, 8, 7, 4, 9, 9, 7, 0, 4]),
+            country_id: None,
+        }
+    );
+    assert_eq!(validated.passport_id.to_string(), "087499704");
+    assert_eq!(validated.hair_color.to_string(), "#623a2f");
+    assert_eq!(validated.height.to_string(), "74in");
+}
+
+#[test]
+fn test_validated_passport_collects_all_errors() {
+    let passport = Passport::from_string(
+        "hgt:59cm ecl:zzz\neyr:2038 hcl:74454a iyr:2023\npid:3556412378 byr:2007",
+    );
+    let errors = ValidatedPassport::try_from(&passport).unwrap_err();
+    let keys: Vec<&str> = errors.iter().map(|error| error.key).collect();
+    assert_eq!(keys, ["byr", "iyr", "eyr", "hgt", "hcl", "ecl", "pid"]);
+    assert_eq!(
+        errors[3].to_string(),
+        "hgt: \"59cm\": not within 150 and 193 cm"
+    );
+
+    let errors = ValidatedPassport::try_from(&Passport::from_string("cid:1 ecl:blu")).unwrap_err();
+    assert_eq!(errors.len(), 6);
+    assert_eq!(errors[0].to_string(), "byr: missing");
+}
+
+#[test]
+fn test_validated_matches_schema() {
+    // the puzzle's part 2 examples, plus values at and beyond the bounds of each rule
+    let input =
+        "eyr:1972 cid:100\r\nhcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926\r\n  \r\n\
+                 iyr:2019 hcl:#602927 eyr:1967 hgt:170cm ecl:grn pid:012533040 byr:1946\n\n\
+                 hcl:dab227 iyr:2012 ecl:brn hgt:182cm pid:021572410 eyr:2020 byr:1992 cid:277\n\n\
+                 hgt:59cm ecl:zzz eyr:2038 hcl:74454a iyr:2023 pid:3556412378 byr:2007\n\n\
+                 pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980 hcl:#623a2f\n\n\
+                 eyr:2029 ecl:blu cid:129 byr:1989 iyr:2014 pid:896056539 hcl:#a97842 hgt:165cm\n\n\
+                 hcl:#888785 hgt:164cm byr:2001 iyr:2015 cid:88 pid:545766238 ecl:hzl eyr:2022\n\n\
+                 iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719\n\n\
+                 byr:1920 iyr:2010 eyr:2030 hgt:59in hcl:#000000 ecl:oth pid:000000000\n\n\
+                 byr:2002 iyr:2020 eyr:2020 hgt:193cm hcl:#ffffff ecl:hzl pid:999999999 nat:x\n\n\
+                 byr:2003 iyr:2010 eyr:2030 hgt:150cm hcl:#000000 ecl:oth pid:000000000\n\n\
+                 byr:1980 iyr:2010 eyr:2030 hgt:77in hcl:#000000 ecl:oth pid:000000000\n\n\
+                 byr:1980 iyr:2010 eyr:2030 hgt:180cm hcl:#00000G ecl:oth pid:000000000\n\n\
+                 byr:1980 iyr:2010 eyr:2030 hgt:180cm hcl:#000000 ecl:oth pid:0000000001\n\n\
+                 byr:1980 iyr:2010 eyr:2030 hgt:180cm hcl:#000000 ecl:oth\n";
+    let (records, _) = crate::records::read_records(input, &Default::default()).unwrap();
+    assert_eq!(records.len(), 15);
+    let mut valid_count = 0;
+    for record in &records {
+        let passport = &record.passport;
+        let validated = ValidatedPassport::try_from(passport);
+        assert_eq!(
+            validated.is_ok(),
+            passport.is_valid_part2(),
+            "{:?}",
+            passport
+        );
+        valid_count += validated.is_ok() as usize;
+    }
+    assert_eq!(valid_count, 6);
+}
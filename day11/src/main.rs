@@ -1,13 +1,17 @@
+use grid::{CellChar, ImageFormat, Rgb};
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::env;
 use std::fmt;
 use std::fs::File;
-use std::io::prelude::Read;
+use std::io::prelude::{Read, Write};
+use std::io::BufWriter;
 #[cfg(test)]
 use std::iter::FromIterator;
 use std::ops::{Index, IndexMut};
+use std::path::Path;
 
-pub type Point2D = (usize, usize);
+pub type Point2D = grid::Point2D;
 
 #[derive(PartialEq, Clone, Debug)]
 pub enum State {
@@ -16,146 +20,116 @@ pub enum State {
     Floor,
 }
 
-impl State {
-    fn from(c: char) -> State {
+impl CellChar for State {
+    fn from_char(c: char) -> Option<State> {
         match c {
-            'L' => State::Empty,
-            '#' => State::Occupied,
-            '.' => State::Floor,
-            _ => panic!("invalid char"),
+            'L' => Some(State::Empty),
+            '#' => Some(State::Occupied),
+            '.' => Some(State::Floor),
+            _ => None,
+        }
+    }
+
+    fn to_char(&self) -> char {
+        match self {
+            State::Empty => 'L',
+            State::Occupied => '#',
+            State::Floor => '.',
+        }
+    }
+}
+
+/// Colors for `Grid::colors`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    pub empty: Rgb,
+    pub occupied: Rgb,
+    pub floor: Rgb,
+    /// Seats that changed in the last transition, if highlighted.
+    pub changed: Rgb,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette {
+            empty: [90, 160, 90],
+            occupied: [200, 60, 50],
+            floor: [235, 235, 235],
+            changed: [250, 200, 40],
         }
     }
 }
 
 pub struct Grid {
-    cells: Vec<State>,
-    width: usize,
-    height: usize,
+    cells: grid::Grid<State>,
     visibility: HashMap<Point2D, HashSet<Point2D>>,
+    /// Cells whose state changed in the last transition.
+    changed: HashSet<Point2D>,
 }
 
 impl Index<&Point2D> for Grid {
     type Output = State;
 
     fn index(&self, point: &Point2D) -> &Self::Output {
-        &self.cells[point.0 + self.width * point.1]
+        &self.cells[point]
     }
 }
 
 impl IndexMut<&Point2D> for Grid {
     fn index_mut(&mut self, point: &Point2D) -> &mut Self::Output {
-        &mut self.cells[point.0 + self.width * point.1]
+        &mut self.cells[point]
     }
 }
 
 impl fmt::Display for Grid {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for y in 0..self.height {
-            for x in 0..self.width {
-                match self[&(x, y)] {
-                    State::Empty => write!(f, "L")?,
-                    State::Occupied => write!(f, "#")?,
-                    State::Floor => write!(f, ".")?,
-                };
-            }
-            write!(f, "\n")?;
-        }
-        Ok(())
+        self.cells.fmt(f)
     }
 }
 
 impl Grid {
     pub fn from(grid_str: &str) -> Grid {
-        let mut cells = Vec::new();
-        let mut height = 0;
-        let mut width = 0;
-        for grid_line in grid_str.split('\n') {
-            if grid_line.is_empty() {
-                continue;
-            }
-            for grid_char in grid_line.chars() {
-                cells.push(State::from(grid_char));
-                if height == 0 {
-                    width += 1; // only increment width for first iteration
-                }
-            }
-            height += 1;
-        }
-
         let mut grid = Grid {
-            cells,
-            width,
-            height,
+            cells: grid::Grid::from_string(grid_str),
             visibility: HashMap::new(),
+            changed: HashSet::new(),
         };
         grid.finalize_visibility();
 
-        return grid;
+        grid
     }
 
     pub fn finalize_visibility(&mut self) {
-        for y in 0..self.height {
-            'cells: for x in 0..self.width {
-                if self[&(x, y)] == State::Floor {
-                    continue 'cells;
-                }
+        for position in self.cells.points() {
+            if self[&position] == State::Floor {
+                continue;
+            }
+
+            let mut visible = HashSet::new();
+
+            for ray_dir_y in [-1, 0, 1].iter() {
+                'rays: for ray_dir_x in [-1, 0, 1].iter() {
+                    if *ray_dir_x == 0 && *ray_dir_y == 0 {
+                        continue 'rays; // discard invalid direction
+                    }
 
-                self.visibility.insert((x, y), HashSet::new());
-
-                for ray_dir_y in [-1, 0, 1].iter() {
-                    'rays: for ray_dir_x in [-1, 0, 1].iter() {
-                        if *ray_dir_x == 0 && *ray_dir_y == 0 {
-                            continue 'rays; // discard invalid direction
-                        }
-
-                        'go_along_ray: for range in 1.. {
-                            let next_cell_on_ray_i32 =
-                                (x as i32 + range * ray_dir_x, y as i32 + range * ray_dir_y); // find potential next pt on ray
-                            if !self.is_valid_point(next_cell_on_ray_i32.0, next_cell_on_ray_i32.1)
-                            {
-                                break 'go_along_ray;
-                            }
-
-                            let next_cell_on_ray = (
-                                next_cell_on_ray_i32.0 as usize,
-                                next_cell_on_ray_i32.1 as usize,
-                            );
-                            if self[&next_cell_on_ray] != State::Floor {
-                                // hit obstacle: add to visibility set and break this ray's loop
-                                self.visibility
-                                    .entry((x, y))
-                                    .or_insert_with(HashSet::new)
-                                    .insert(next_cell_on_ray);
-                                break 'go_along_ray;
-                            }
-                        }
+                    // the first obstacle on the ray is visible
+                    if let Some((obstacle, _)) = self
+                        .cells
+                        .cast_ray(position, (*ray_dir_x, *ray_dir_y))
+                        .find(|(_, state)| **state != State::Floor)
+                    {
+                        visible.insert(obstacle);
                     }
                 }
             }
-        }
-    }
 
-    fn is_valid_point(&self, x: i32, y: i32) -> bool {
-        x < self.width as i32 && x >= 0 && y < self.height as i32 && y >= 0
+            self.visibility.insert(position, visible);
+        }
     }
 
     fn get_adjacent_positions(&self, position: &Point2D) -> HashSet<Point2D> {
-        let mut neighbors = HashSet::new();
-        for y_offset in [-1, 0, 1].iter() {
-            for x_offset in [-1, 0, 1].iter() {
-                if *x_offset == 0 && *y_offset == 0 {
-                    continue;
-                }
-                let neighbor_x = position.0 as i32 + x_offset;
-                let neighbor_y = position.1 as i32 + y_offset;
-
-                // check under/overflow
-                if self.is_valid_point(neighbor_x, neighbor_y) {
-                    neighbors.insert((neighbor_x as usize, neighbor_y as usize));
-                }
-            }
-        }
-        return neighbors;
+        self.cells.neighbors(*position).collect()
     }
 
     /// Returns the cell's new state or none, if its state won't change.
@@ -201,23 +175,19 @@ impl Grid {
         let mut new_states = HashMap::<Point2D, State>::new();
 
         // Find which cells need new states
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let current_position = (x, y);
-                let new_state;
-                if part1 {
-                    let direct_neighbors = self.get_adjacent_positions(&current_position);
-                    new_state = self.next_cell_state(&current_position, &direct_neighbors, 4);
-                } else {
-                    if self[&current_position] == State::Floor {
-                        continue; // floor never changes
-                    }
-                    let visibility_neighbors = &self.visibility[&current_position];
-                    new_state = self.next_cell_state(&current_position, visibility_neighbors, 5);
-                }
-                if new_state.is_some() {
-                    new_states.insert(current_position, new_state.unwrap());
+        for current_position in self.cells.points() {
+            let new_state = if part1 {
+                let direct_neighbors = self.get_adjacent_positions(&current_position);
+                self.next_cell_state(&current_position, &direct_neighbors, 4)
+            } else {
+                if self[&current_position] == State::Floor {
+                    continue; // floor never changes
                 }
+                let visibility_neighbors = &self.visibility[&current_position];
+                self.next_cell_state(&current_position, visibility_neighbors, 5)
+            };
+            if let Some(new_state) = new_state {
+                new_states.insert(current_position, new_state);
             }
         }
 
@@ -225,9 +195,44 @@ impl Grid {
         for (position, state) in new_states.iter() {
             self[position] = state.clone();
         }
+        self.changed = new_states.keys().cloned().collect();
 
-        return new_states.len();
+        new_states.len()
     }
+
+    /// The seats' colors, with the changes of the last transition highlighted if `highlight_changes` is set.
+    /// Write it with `grid::Grid::write_image`.
+    pub fn colors(&self, palette: &Palette, highlight_changes: bool) -> grid::Grid<Rgb> {
+        self.cells.map(|point, state| {
+            if highlight_changes && self.changed.contains(&point) {
+                return palette.changed;
+            }
+            match state {
+                State::Empty => palette.empty,
+                State::Occupied => palette.occupied,
+                State::Floor => palette.floor,
+            }
+        })
+    }
+}
+
+/// `image <file> <transitions> [cell_size]` writes the part 2 state after some transitions,
+/// with the last transition's changes highlighted. The format is taken from the file extension.
+fn write_image(grid: &mut Grid, args: &[String]) {
+    let path = Path::new(&args[0]);
+    let format = ImageFormat::from_path(path).expect("unknown image format, use png, ppm or svg");
+    let transitions: usize = args[1].parse().expect("invalid number of transitions");
+    let cell_size: usize = args
+        .get(2)
+        .map_or(8, |size| size.parse().expect("invalid cell size"));
+    for _ in 0..transitions {
+        grid.transition(false);
+    }
+    let mut writer = BufWriter::new(File::create(path).unwrap());
+    grid.colors(&Palette::default(), true)
+        .write_image(format, cell_size, &mut writer)
+        .unwrap();
+    writer.flush().unwrap();
 }
 
 fn main() {
@@ -236,6 +241,12 @@ fn main() {
     file.read_to_string(&mut input_string).unwrap();
     let mut grid = Grid::from(&input_string);
 
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(|mode| mode.as_str()) == Some("image") {
+        write_image(&mut grid, &args[1..]);
+        return;
+    }
+
     while grid.transition(false) != 0 {}
 
     println!(
@@ -378,8 +389,46 @@ fn test_part1_example() {
 fn test_grid_from_str_and_formatting() {
     let input_str = "L.LL.LL.LL\nLLLLLLL.LL\nL.L.L..L..\nLLLL.LL.LL\nL.LL.LL.LL\nL.LLLLL.LL\n..L.L.....\nLLLLLLLLLL\nL.LLLLLL.L\nL.LLLLL.LL\n";
     let grid = Grid::from(input_str);
-    assert_eq!(grid.width, 10);
-    assert_eq!(grid.height, 10);
+    assert_eq!(grid.cells.width(), 10);
+    assert_eq!(grid.cells.height(), 10);
     println!("{}", grid);
     //assert_eq!(false, true);
 }
+
+#[test]
+fn test_colors_highlight_changes() {
+    let mut grid = Grid::from("L.\n.L\n");
+    let palette = Palette::default();
+    assert_eq!(
+        grid.colors(&palette, true)
+            .iter()
+            .cloned()
+            .collect::<Vec<Rgb>>(),
+        [palette.empty, palette.floor, palette.floor, palette.empty]
+    );
+    assert_eq!(grid.transition(true), 2);
+    assert_eq!(
+        grid.colors(&palette, true)
+            .iter()
+            .cloned()
+            .collect::<Vec<Rgb>>(),
+        [
+            palette.changed,
+            palette.floor,
+            palette.floor,
+            palette.changed
+        ]
+    );
+    assert_eq!(
+        grid.colors(&palette, false)
+            .iter()
+            .cloned()
+            .collect::<Vec<Rgb>>(),
+        [
+            palette.occupied,
+            palette.floor,
+            palette.floor,
+            palette.occupied
+        ]
+    );
+}
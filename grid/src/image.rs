@@ -0,0 +1,340 @@
+use crate::Grid;
+use std::convert::TryFrom;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Red, green and blue.
+pub type Rgb = [u8; 3];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageFormat {
+    Png,
+    Ppm,
+    Svg,
+}
+
+impl ImageFormat {
+    pub fn from_path(path: &Path) -> Option<ImageFormat> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "png" => Some(ImageFormat::Png),
+            "ppm" => Some(ImageFormat::Ppm),
+            "svg" => Some(ImageFormat::Svg),
+            _ => None,
+        }
+    }
+}
+
+/// Colored grids, e.g. from `Grid::map` with a palette, can be written as images
+/// with each cell being a `cell_size` by `cell_size` square.
+impl Grid<Rgb> {
+    pub fn write_image(
+        &self,
+        format: ImageFormat,
+        cell_size: usize,
+        writer: &mut impl Write,
+    ) -> io::Result<()> {
+        match format {
+            ImageFormat::Png => self.write_png(cell_size, writer),
+            ImageFormat::Ppm => self.write_ppm(cell_size, writer),
+            ImageFormat::Svg => self.write_svg(cell_size, writer),
+        }
+    }
+
+    /// Pixel rows, top to bottom.
+    fn scanlines(&self, cell_size: usize) -> impl Iterator<Item = Vec<u8>> + '_ {
+        self.rows().flat_map(move |row| {
+            let scanline: Vec<u8> = row
+                .iter()
+                .flat_map(|color| std::iter::repeat_n(color, cell_size).flatten())
+                .cloned()
+                .collect();
+            std::iter::repeat_n(scanline, cell_size)
+        })
+    }
+
+    /// Binary PPM (`P6`).
+    pub fn write_ppm(&self, cell_size: usize, writer: &mut impl Write) -> io::Result<()> {
+        write!(
+            writer,
+            "P6\n{} {}\n255\n",
+            self.width() * cell_size,
+            self.height() * cell_size
+        )?;
+        for scanline in self.scanlines(cell_size) {
+            writer.write_all(&scanline)?;
+        }
+        Ok(())
+    }
+
+    /// 8 bit RGB PNG. The image data isn't compressed, so that no deflate implementation is needed.
+    ///
+    /// Fails with `io::ErrorKind::InvalidInput` if the image would be empty or too large for PNG.
+    pub fn write_png(&self, cell_size: usize, writer: &mut impl Write) -> io::Result<()> {
+        let width = png_dimension(self.width(), cell_size)?;
+        let height = png_dimension(self.height(), cell_size)?;
+        let mut raw = Vec::new();
+        for scanline in self.scanlines(cell_size) {
+            raw.push(0); // no filter
+            raw.extend(scanline);
+        }
+
+        let mut header = Vec::new();
+        header.extend(&width.to_be_bytes());
+        header.extend(&height.to_be_bytes());
+        header.extend(&[8, 2, 0, 0, 0]); // bit depth, RGB, compression, filter, no interlace
+
+        writer.write_all(b"\x89PNG\r\n\x1a\n")?;
+        write_png_chunk(writer, b"IHDR", &header)?;
+        write_png_chunks(writer, b"IDAT", &zlib_stored(&raw), MAX_CHUNK_LENGTH)?;
+        write_png_chunk(writer, b"IEND", &[])
+    }
+
+    /// One `rect` per cell.
+    pub fn write_svg(&self, cell_size: usize, writer: &mut impl Write) -> io::Result<()> {
+        let (width, height) = (self.width() * cell_size, self.height() * cell_size);
+        writeln!(
+            writer,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}" shape-rendering="crispEdges">"#,
+            width, height, width, height
+        )?;
+        for point in self.points() {
+            let [r, g, b] = self[point];
+            writeln!(
+                writer,
+                r##"<rect x="{}" y="{}" width="{}" height="{}" fill="#{:02x}{:02x}{:02x}"/>"##,
+                point.0 * cell_size,
+                point.1 * cell_size,
+                cell_size,
+                cell_size,
+                r,
+                g,
+                b
+            )?;
+        }
+        writeln!(writer, "</svg>")
+    }
+}
+
+/// Pixels along one axis, which PNG requires to be between 1 and 2^31 - 1.
+fn png_dimension(cells: usize, cell_size: usize) -> io::Result<u32> {
+    let pixels = u32::try_from(cells)
+        .ok()
+        .zip(u32::try_from(cell_size).ok())
+        .and_then(|(cells, cell_size)| cells.checked_mul(cell_size))
+        .filter(|&pixels| pixels <= i32::MAX as u32);
+    match pixels {
+        Some(0) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "PNG images can't be empty",
+        )),
+        Some(pixels) => Ok(pixels),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{} cells of {} pixels are too large for PNG",
+                cells, cell_size
+            ),
+        )),
+    }
+}
+
+/// PNG chunks hold at most 2^31 - 1 bytes.
+const MAX_CHUNK_LENGTH: usize = i32::MAX as usize;
+
+/// Splits `data` over as many chunks of `kind` as needed, which PNG allows for `IDAT`.
+fn write_png_chunks(
+    writer: &mut impl Write,
+    kind: &[u8; 4],
+    data: &[u8],
+    max_length: usize,
+) -> io::Result<()> {
+    for chunk in data.chunks(max_length.min(MAX_CHUNK_LENGTH)) {
+        write_png_chunk(writer, kind, chunk)?;
+    }
+    Ok(())
+}
+
+fn write_png_chunk(writer: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    debug_assert!(data.len() <= MAX_CHUNK_LENGTH);
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(kind)?;
+    writer.write_all(data)?;
+    let crc = crc32(kind.iter().chain(data));
+    writer.write_all(&crc.to_be_bytes())
+}
+
+/// A zlib stream of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut stream = vec![0x78, 0x01];
+    let mut blocks = data.chunks(u16::MAX as usize).peekable();
+    if blocks.peek().is_none() {
+        stream.extend(&[1, 0, 0, 0xff, 0xff]); // a single empty final block
+    }
+    while let Some(block) = blocks.next() {
+        stream.push(blocks.peek().is_none() as u8);
+        stream.extend(&(block.len() as u16).to_le_bytes());
+        stream.extend(&(!(block.len() as u16)).to_le_bytes());
+        stream.extend(block);
+    }
+    stream.extend(&adler32(data).to_be_bytes());
+    stream
+}
+
+fn crc32<'a>(bytes: impl Iterator<Item = &'a u8>) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0xedb8_8320,
+                _ => crc >> 1,
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in bytes.chunks(5552) {
+        // the largest chunk for which `b` can't overflow before the modulo
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+#[test]
+fn test_checksums() {
+    assert_eq!(crc32(b"IEND".iter()), 0xae42_6082);
+    assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    assert_eq!(adler32(&vec![0xff; 100_000]), 0x149a_302c);
+}
+
+#[test]
+fn test_zlib_stored() {
+    assert_eq!(zlib_stored(b""), [0x78, 1, 1, 0, 0, 0xff, 0xff, 0, 0, 0, 1]);
+    let stream = zlib_stored(&vec![7; 70_000]);
+    assert_eq!(stream.len(), 2 + 5 + 65535 + 5 + 4465 + 4);
+    assert_eq!(stream[2..7], [0, 0xff, 0xff, 0, 0]);
+    assert_eq!(stream[65542..65547], [1, 0x71, 0x11, 0x8e, 0xee]);
+}
+
+#[cfg(test)]
+fn test_image() -> Grid<Rgb> {
+    Grid::new(2, 1, vec![[255, 0, 0], [0, 0, 255]])
+}
+
+#[test]
+fn test_write_ppm() {
+    let mut ppm = Vec::new();
+    test_image().write_ppm(2, &mut ppm).unwrap();
+    let mut expected = b"P6\n4 2\n255\n".to_vec();
+    for _ in 0..2 {
+        expected.extend(&[255, 0, 0, 255, 0, 0, 0, 0, 255, 0, 0, 255]);
+    }
+    assert_eq!(ppm, expected);
+}
+
+#[test]
+fn test_write_png() {
+    let mut png = Vec::new();
+    test_image().write_png(1, &mut png).unwrap();
+    assert_eq!(png[..8], *b"\x89PNG\r\n\x1a\n");
+    assert_eq!(png[8..16], [0, 0, 0, 13, b'I', b'H', b'D', b'R']);
+    assert_eq!(png[16..29], [0, 0, 0, 2, 0, 0, 0, 1, 8, 2, 0, 0, 0]);
+    assert_eq!(png[33..41], [0, 0, 0, 18, b'I', b'D', b'A', b'T']);
+    // zlib header, final stored block of 7 bytes, filter byte and pixels
+    assert_eq!(
+        png[41..55],
+        [0x78, 1, 1, 7, 0, 0xf8, 0xff, 0, 255, 0, 0, 0, 0, 255]
+    );
+    assert_eq!(
+        png[png.len() - 12..],
+        [0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]
+    );
+}
+
+#[test]
+fn test_write_png_chunks() {
+    let mut png = Vec::new();
+    write_png_chunks(&mut png, b"IDAT", &[1, 2, 3, 4, 5], 2).unwrap();
+    let mut expected = Vec::new();
+    for data in [&[1, 2][..], &[3, 4], &[5]].iter() {
+        write_png_chunk(&mut expected, b"IDAT", data).unwrap();
+    }
+    assert_eq!(png, expected);
+    assert_eq!(png[..10], [0, 0, 0, 2, b'I', b'D', b'A', b'T', 1, 2]);
+    assert_eq!(png.len(), 3 * 12 + 5);
+}
+
+#[test]
+fn test_write_png_invalid_size() {
+    let write_png = |grid: &Grid<Rgb>, cell_size| {
+        let mut png = Vec::new();
+        let error = grid.write_png(cell_size, &mut png).unwrap_err();
+        assert!(png.is_empty());
+        (error.kind(), error.to_string())
+    };
+    assert_eq!(
+        write_png(&test_image(), 0),
+        (
+            io::ErrorKind::InvalidInput,
+            "PNG images can't be empty".to_string()
+        )
+    );
+    assert_eq!(
+        write_png(&Grid::new(0, 0, vec![]), 4).0,
+        io::ErrorKind::InvalidInput
+    );
+    // 2 * 2^31 pixels, which would wrap to 0 as u32
+    assert_eq!(
+        write_png(&test_image(), 1 << 31),
+        (
+            io::ErrorKind::InvalidInput,
+            "2 cells of 2147483648 pixels are too large for PNG".to_string()
+        )
+    );
+    assert_eq!(
+        write_png(&test_image(), usize::MAX).0,
+        io::ErrorKind::InvalidInput
+    );
+    assert_eq!(
+        write_png(&Grid::new(1, 70_000, vec![[0; 3]; 70_000]), 40_000).0,
+        io::ErrorKind::InvalidInput
+    );
+}
+
+#[test]
+fn test_write_svg() {
+    let mut svg = Vec::new();
+    test_image().write_svg(3, &mut svg).unwrap();
+    assert_eq!(
+        String::from_utf8(svg).unwrap(),
+        concat!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="6" height="3" viewBox="0 0 6 3" shape-rendering="crispEdges">"#,
+            "\n",
+            r##"<rect x="0" y="0" width="3" height="3" fill="#ff0000"/>"##,
+            "\n",
+            r##"<rect x="3" y="0" width="3" height="3" fill="#0000ff"/>"##,
+            "\n</svg>\n"
+        )
+    );
+}
+
+#[test]
+fn test_image_format_from_path() {
+    assert_eq!(
+        ImageFormat::from_path(Path::new("map.PNG")),
+        Some(ImageFormat::Png)
+    );
+    assert_eq!(
+        ImageFormat::from_path(Path::new("out/map.svg")),
+        Some(ImageFormat::Svg)
+    );
+    assert_eq!(ImageFormat::from_path(Path::new("map")), None);
+}
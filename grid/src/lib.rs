@@ -0,0 +1,317 @@
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+mod image;
+mod parse;
+mod ray;
+
+pub use image::{ImageFormat, Rgb};
+pub use parse::{GridParseError, ParseOptions, SymbolTable};
+pub use ray::{Direction, Ray, RayMode};
+
+/// `(x, y)`, with `(0, 0)` being the top left cell.
+pub type Point2D = (usize, usize);
+
+/// What happens to points that leave the grid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Boundary {
+    /// Points outside of the grid don't exist.
+    Bounded,
+    /// The grid repeats to the left and right, but not above and below.
+    WrapHorizontal,
+    /// The grid repeats in all directions.
+    Toroidal,
+}
+
+/// Cells that can be parsed from and printed as a single char.
+pub trait CellChar: Sized {
+    fn from_char(c: char) -> Option<Self>;
+    fn to_char(&self) -> char;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid<T> {
+    cells: Vec<T>,
+    width: usize,
+    height: usize,
+    boundary: Boundary,
+}
+
+impl<T> Grid<T> {
+    /// Cells are given row by row. The grid is `Boundary::Bounded`.
+    pub fn new(width: usize, height: usize, cells: Vec<T>) -> Grid<T> {
+        assert_eq!(cells.len(), width * height, "cell count doesn't match size");
+        Grid {
+            cells,
+            width,
+            height,
+            boundary: Boundary::Bounded,
+        }
+    }
+
+    pub fn with_boundary(mut self, boundary: Boundary) -> Grid<T> {
+        self.boundary = boundary;
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    /// All cells, row by row.
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.cells.iter()
+    }
+
+    /// A grid of the same size with each cell replaced, e.g. by a color.
+    pub fn map<U>(&self, mut cell: impl FnMut(Point2D, &T) -> U) -> Grid<U> {
+        let cells = self
+            .points()
+            .zip(self.cells.iter())
+            .map(|(point, value)| cell(point, value))
+            .collect();
+        Grid::new(self.width, self.height, cells).with_boundary(self.boundary)
+    }
+
+    /// All points, row by row.
+    pub fn points(&self) -> impl Iterator<Item = Point2D> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    pub fn get(&self, point: Point2D) -> Option<&T> {
+        match point.0 < self.width && point.1 < self.height {
+            true => Some(&self.cells[point.0 + self.width * point.1]),
+            false => None,
+        }
+    }
+
+    /// Maps possibly outside coordinates into the grid according to its boundary.
+    /// Returns `None` if the point doesn't exist.
+    pub fn resolve(&self, x: isize, y: isize) -> Option<Point2D> {
+        if self.width == 0 || self.height == 0 {
+            return None;
+        }
+        let wrap = |value: isize, size: usize| value.rem_euclid(size as isize) as usize;
+        let bounded = |value: isize, size: usize| match value >= 0 && (value as usize) < size {
+            true => Some(value as usize),
+            false => None,
+        };
+        match self.boundary {
+            Boundary::Bounded => Some((bounded(x, self.width)?, bounded(y, self.height)?)),
+            Boundary::WrapHorizontal => Some((wrap(x, self.width), bounded(y, self.height)?)),
+            Boundary::Toroidal => Some((wrap(x, self.width), wrap(y, self.height))),
+        }
+    }
+
+    /// The point moved by `(dx, dy)`, see `resolve`.
+    pub fn offset(&self, point: Point2D, dx: isize, dy: isize) -> Option<Point2D> {
+        self.resolve(point.0 as isize + dx, point.1 as isize + dy)
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.height).map(move |y| self.row(y))
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        assert!(x < self.width, "column out of range");
+        self.cells.iter().skip(x).step_by(self.width)
+    }
+
+    /// The up to 8 surrounding points, row by row. On small wrapping grids, each point is only returned once,
+    /// and the point itself is never returned.
+    pub fn neighbors(&self, point: Point2D) -> impl Iterator<Item = Point2D> {
+        let mut neighbors = Vec::with_capacity(8);
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                if let Some(neighbor) = self.offset(point, dx, dy) {
+                    if neighbor != point && !neighbors.contains(&neighbor) {
+                        neighbors.push(neighbor);
+                    }
+                }
+            }
+        }
+        neighbors.into_iter()
+    }
+
+    /// Moves from `start` by `direction` until the point doesn't exist anymore, see `resolve`.
+    /// The start itself isn't visited. A ray that can't leave the grid, e.g. one with `dy == 0` on a
+    /// horizontally wrapping grid, ends after one full period, just before it would be back at the start.
+    ///
+    /// Panics if `direction` is `(0, 0)`.
+    pub fn cast_ray(&self, start: Point2D, direction: Direction) -> Ray<'_, T> {
+        Ray::new(self, start, direction, RayMode::Lattice)
+    }
+
+    /// Like `cast_ray`, but visits every cell along the line towards `direction`, one step on the longer axis
+    /// at a time, see `RayMode::Bresenham`.
+    pub fn cast_line(&self, start: Point2D, direction: Direction) -> Ray<'_, T> {
+        Ray::new(self, start, direction, RayMode::Bresenham)
+    }
+}
+
+impl<T: CellChar> Grid<T> {
+    /// Parses one row per line with `CellChar::from_char`, see `parse_with`.
+    pub fn parse(grid_string: &str) -> Result<Grid<T>, GridParseError> {
+        Grid::parse_with(grid_string, &ParseOptions::default(), T::from_char)
+    }
+
+    /// Like `parse`, but panics on invalid input.
+    pub fn from_string(grid_string: &str) -> Grid<T> {
+        Grid::parse(grid_string).unwrap_or_else(|error| panic!("{}", error))
+    }
+}
+
+impl<T> Index<Point2D> for Grid<T> {
+    type Output = T;
+    fn index(&self, point: Point2D) -> &Self::Output {
+        &self.cells[point.0 + self.width * point.1]
+    }
+}
+
+impl<T> Index<&Point2D> for Grid<T> {
+    type Output = T;
+    fn index(&self, point: &Point2D) -> &Self::Output {
+        &self[*point]
+    }
+}
+
+impl<T> IndexMut<Point2D> for Grid<T> {
+    fn index_mut(&mut self, point: Point2D) -> &mut Self::Output {
+        &mut self.cells[point.0 + self.width * point.1]
+    }
+}
+
+impl<T> IndexMut<&Point2D> for Grid<T> {
+    fn index_mut(&mut self, point: &Point2D) -> &mut Self::Output {
+        &mut self[*point]
+    }
+}
+
+impl<T: CellChar> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.rows() {
+            for cell in row {
+                write!(f, "{}", cell.to_char())?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[derive(Debug, Clone, PartialEq)]
+enum TestCell {
+    Off,
+    On,
+}
+
+#[cfg(test)]
+impl CellChar for TestCell {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            '.' => Some(TestCell::Off),
+            '#' => Some(TestCell::On),
+            _ => None,
+        }
+    }
+
+    fn to_char(&self) -> char {
+        match self {
+            TestCell::Off => '.',
+            TestCell::On => '#',
+        }
+    }
+}
+
+#[test]
+fn test_from_string_and_display() {
+    let grid = Grid::<TestCell>::from_string(".#..\n#.#.\n\n..#.\n");
+    assert_eq!(grid.width(), 4);
+    assert_eq!(grid.height(), 3);
+    assert_eq!(grid[(1, 0)], TestCell::On);
+    assert_eq!(grid[&(2, 2)], TestCell::On);
+    assert_eq!(grid.get((3, 2)), Some(&TestCell::Off));
+    assert_eq!(grid.get((4, 0)), None);
+    assert_eq!(grid.to_string(), ".#..\n#.#.\n..#.\n");
+    assert_eq!(Grid::<TestCell>::from_string(""), Grid::new(0, 0, vec![]));
+}
+
+#[test]
+#[should_panic(expected = "invalid cell char 'x' at row 1, column 2")]
+fn test_from_string_invalid_char() {
+    Grid::<TestCell>::from_string(".x");
+}
+
+#[test]
+#[should_panic(expected = "row 2 has a different length (3 instead of 2)")]
+fn test_from_string_ragged_rows() {
+    Grid::<TestCell>::from_string("..\n...");
+}
+
+#[test]
+fn test_boundaries() {
+    let grid = Grid::new(3, 2, vec![0; 6]);
+    assert_eq!(grid.resolve(2, 1), Some((2, 1)));
+    assert_eq!(grid.resolve(3, 1), None);
+    assert_eq!(grid.resolve(-1, 0), None);
+
+    let grid = grid.with_boundary(Boundary::WrapHorizontal);
+    assert_eq!(grid.resolve(3, 1), Some((0, 1)));
+    assert_eq!(grid.resolve(-1, 0), Some((2, 0)));
+    assert_eq!(grid.resolve(0, 2), None);
+
+    let grid = grid.with_boundary(Boundary::Toroidal);
+    assert_eq!(grid.resolve(-4, -3), Some((2, 1)));
+    assert_eq!(grid.offset((2, 1), 1, 1), Some((0, 0)));
+    assert_eq!(Grid::<u8>::new(0, 0, vec![]).resolve(0, 0), None);
+}
+
+#[test]
+fn test_rows_columns_and_points() {
+    let mut grid = Grid::new(3, 2, (0..6).collect());
+    assert_eq!(grid.rows().collect::<Vec<&[i32]>>(), [[0, 1, 2], [3, 4, 5]]);
+    assert_eq!(grid.column(1).collect::<Vec<&i32>>(), [&1, &4]);
+    assert_eq!(
+        grid.points().collect::<Vec<Point2D>>(),
+        [(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1)]
+    );
+    assert_eq!(
+        grid.map(|(x, y), value| x * 10 + y + *value as usize),
+        Grid::new(3, 2, vec![0, 11, 22, 4, 15, 26])
+    );
+    grid[(2, 1)] = 7;
+    grid[&(0, 0)] += 1;
+    assert_eq!(
+        grid.iter().cloned().collect::<Vec<i32>>(),
+        [1, 1, 2, 3, 4, 7]
+    );
+}
+
+#[test]
+fn test_neighbors() {
+    let grid = Grid::new(3, 3, vec![0; 9]);
+    assert_eq!(
+        grid.neighbors((0, 0)).collect::<Vec<Point2D>>(),
+        [(1, 0), (0, 1), (1, 1)]
+    );
+    assert_eq!(grid.neighbors((1, 1)).count(), 8);
+
+    let grid = grid.with_boundary(Boundary::Toroidal);
+    assert_eq!(grid.neighbors((0, 0)).count(), 8);
+    let grid = Grid::new(2, 1, vec![0; 2]).with_boundary(Boundary::Toroidal);
+    assert_eq!(grid.neighbors((0, 0)).collect::<Vec<Point2D>>(), [(1, 0)]);
+}
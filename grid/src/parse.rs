@@ -0,0 +1,224 @@
+use crate::Grid;
+use std::error::Error;
+use std::fmt;
+
+/// Rows and columns are 1-based and count chars in the input, including skipped empty lines.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GridParseError {
+    InvalidChar {
+        row: usize,
+        column: usize,
+        found: char,
+    },
+    RaggedRow {
+        row: usize,
+        expected_width: usize,
+        width: usize,
+    },
+}
+
+impl fmt::Display for GridParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GridParseError::InvalidChar { row, column, found } => write!(
+                f,
+                "invalid cell char {:?} at row {}, column {}",
+                found, row, column
+            ),
+            GridParseError::RaggedRow {
+                row,
+                expected_width,
+                width,
+            } => write!(
+                f,
+                "row {} has a different length ({} instead of {})",
+                row, width, expected_width
+            ),
+        }
+    }
+}
+
+impl Error for GridParseError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParseOptions {
+    /// Accept `\r\n` line endings. Otherwise, the `\r` is an invalid char.
+    pub allow_crlf: bool,
+    /// Ignore whitespace at the end of rows, e.g. from copy and pasting.
+    pub trim_trailing_whitespace: bool,
+}
+
+impl ParseOptions {
+    /// Every char has to be a cell.
+    pub fn strict() -> ParseOptions {
+        ParseOptions {
+            allow_crlf: false,
+            trim_trailing_whitespace: false,
+        }
+    }
+}
+
+impl Default for ParseOptions {
+    /// Accepts CRLF like `str::lines` does.
+    fn default() -> Self {
+        ParseOptions {
+            allow_crlf: true,
+            trim_trailing_whitespace: false,
+        }
+    }
+}
+
+/// Which char stands for which cell, for inputs that don't use the `CellChar` symbols.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolTable<T> {
+    symbols: Vec<(char, T)>,
+}
+
+impl<T: Clone> SymbolTable<T> {
+    pub fn new() -> SymbolTable<T> {
+        SymbolTable {
+            symbols: Vec::new(),
+        }
+    }
+
+    /// Adds `symbol` for `cell`. A cell can have several symbols.
+    pub fn with(mut self, symbol: char, cell: T) -> SymbolTable<T> {
+        self.symbols.retain(|(known, _)| *known != symbol);
+        self.symbols.push((symbol, cell));
+        self
+    }
+
+    pub fn get(&self, symbol: char) -> Option<T> {
+        self.symbols
+            .iter()
+            .find(|(known, _)| *known == symbol)
+            .map(|(_, cell)| cell.clone())
+    }
+}
+
+impl<T: Clone> Default for SymbolTable<T> {
+    fn default() -> Self {
+        SymbolTable::new()
+    }
+}
+
+impl<T> Grid<T> {
+    /// Parses one row per line, turning chars into cells with `cell`. Empty lines are skipped.
+    pub fn parse_with(
+        grid_string: &str,
+        options: &ParseOptions,
+        cell: impl Fn(char) -> Option<T>,
+    ) -> Result<Grid<T>, GridParseError> {
+        let mut cells = Vec::new();
+        let mut width = 0;
+        let mut height = 0;
+
+        for (line_id, mut grid_line) in grid_string.split('\n').enumerate() {
+            if options.allow_crlf {
+                grid_line = grid_line.strip_suffix('\r').unwrap_or(grid_line);
+            }
+            if options.trim_trailing_whitespace {
+                grid_line = grid_line.trim_end();
+            }
+            if grid_line.is_empty() {
+                continue;
+            }
+            let mut line_length = 0;
+            for cell_char in grid_line.chars() {
+                line_length += 1;
+                match cell(cell_char) {
+                    Some(cell) => cells.push(cell),
+                    None => {
+                        return Err(GridParseError::InvalidChar {
+                            row: line_id + 1,
+                            column: line_length,
+                            found: cell_char,
+                        })
+                    }
+                }
+            }
+            if height > 0 && width != line_length {
+                return Err(GridParseError::RaggedRow {
+                    row: line_id + 1,
+                    expected_width: width,
+                    width: line_length,
+                });
+            }
+            width = line_length;
+            height += 1;
+        }
+
+        Ok(Grid::new(width, height, cells))
+    }
+}
+
+#[test]
+fn test_parse_errors() {
+    let digit = |c: char| c.to_digit(10);
+    assert_eq!(
+        Grid::parse_with("12\n3x\n", &ParseOptions::default(), digit),
+        Err(GridParseError::InvalidChar {
+            row: 2,
+            column: 2,
+            found: 'x'
+        })
+    );
+    let error = Grid::parse_with("12\n\n123\n", &ParseOptions::default(), digit).unwrap_err();
+    assert_eq!(
+        error,
+        GridParseError::RaggedRow {
+            row: 3,
+            expected_width: 2,
+            width: 3
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "row 3 has a different length (3 instead of 2)"
+    );
+}
+
+#[test]
+fn test_parse_options() {
+    let digit = |c: char| c.to_digit(10);
+    let input = "12\r\n34 \r\n";
+    let strict = ParseOptions::strict();
+    assert_eq!(
+        Grid::parse_with(input, &strict, digit),
+        Err(GridParseError::InvalidChar {
+            row: 1,
+            column: 3,
+            found: '\r'
+        })
+    );
+    assert_eq!(
+        Grid::parse_with(input, &ParseOptions::default(), digit),
+        Err(GridParseError::InvalidChar {
+            row: 2,
+            column: 3,
+            found: ' '
+        })
+    );
+    let tolerant = ParseOptions {
+        trim_trailing_whitespace: true,
+        ..strict
+    };
+    assert_eq!(
+        Grid::parse_with(input, &tolerant, digit),
+        Ok(Grid::new(2, 2, vec![1, 2, 3, 4]))
+    );
+}
+
+#[test]
+fn test_symbol_table() {
+    let symbols = SymbolTable::new()
+        .with('.', false)
+        .with('O', true)
+        .with('X', true)
+        .with('.', true);
+    assert_eq!(symbols.get('X'), Some(true));
+    assert_eq!(symbols.get('.'), Some(true));
+    assert_eq!(symbols.get('#'), None);
+    let grid = Grid::parse_with("OX.", &ParseOptions::default(), |c| symbols.get(c)).unwrap();
+    assert_eq!(grid.iter().filter(|tree| **tree).count(), 3);
+}
@@ -0,0 +1,197 @@
+use crate::{Boundary, Grid, Point2D};
+
+/// `(dx, dy)`, positive values go right and down.
+pub type Direction = (isize, isize);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RayMode {
+    /// Jumps by the whole direction each step, skipping the cells in between.
+    Lattice,
+    /// Steps by one cell along the longer axis of the direction and rounds the other axis to the nearest cell,
+    /// so that e.g. `(3, 2)` visits the cells a line with that slope passes through.
+    Bresenham,
+}
+
+/// Cells along a line through a borrowed grid, see `Grid::cast_ray` and `Grid::cast_line`.
+pub struct Ray<'a, T> {
+    grid: &'a Grid<T>,
+    start: Point2D,
+    direction: Direction,
+    mode: RayMode,
+    step: isize,
+    /// Steps after which a ray that can't leave the grid is back at its start.
+    period: Option<isize>,
+    finished: bool,
+}
+
+fn gcd(a: isize, b: isize) -> isize {
+    match b {
+        0 => a,
+        _ => gcd(b, a % b),
+    }
+}
+
+/// Steps until a wrapping axis of `size` cells is back at the same cell.
+fn axis_period(size: usize, d: isize) -> isize {
+    let size = size.max(1) as isize;
+    size / gcd(size, d.abs() % size)
+}
+
+impl<'a, T> Ray<'a, T> {
+    pub(crate) fn new(
+        grid: &'a Grid<T>,
+        start: Point2D,
+        direction: Direction,
+        mode: RayMode,
+    ) -> Ray<'a, T> {
+        assert!(direction != (0, 0), "ray direction must not be (0, 0)");
+        let direction = match mode {
+            RayMode::Lattice => direction,
+            // `(2, 0)` passes through the same cells as `(1, 0)`, but only the reduced direction gives the
+            // shortest period
+            RayMode::Bresenham => {
+                let divisor = gcd(direction.0.abs(), direction.1.abs());
+                (direction.0 / divisor, direction.1 / divisor)
+            }
+        };
+        let (dx, dy) = direction;
+        let (wraps_x, wraps_y) = match grid.boundary() {
+            Boundary::Bounded => (false, false),
+            Boundary::WrapHorizontal => (true, false),
+            Boundary::Toroidal => (true, true),
+        };
+        let period = match (wraps_x || dx == 0) && (wraps_y || dy == 0) {
+            true => {
+                let (x_period, y_period) = (
+                    axis_period(grid.width(), dx),
+                    axis_period(grid.height(), dy),
+                );
+                let lattice_period = x_period / gcd(x_period, y_period) * y_period;
+                Some(match mode {
+                    RayMode::Lattice => lattice_period,
+                    // the offsets repeat after `major` steps, shifted by the whole direction
+                    RayMode::Bresenham => lattice_period * dx.abs().max(dy.abs()),
+                })
+            }
+            false => None,
+        };
+        Ray {
+            grid,
+            start,
+            direction,
+            mode,
+            step: 0,
+            period,
+            finished: false,
+        }
+    }
+
+    /// Offset from the start after `step` steps.
+    fn offset(&self, step: isize) -> Direction {
+        let (dx, dy) = self.direction;
+        match self.mode {
+            RayMode::Lattice => (step * dx, step * dy),
+            RayMode::Bresenham => {
+                let major = dx.abs().max(dy.abs());
+                // rounds half away from the start
+                let minor = |d: isize| d.signum() * ((2 * step * d.abs() + major) / (2 * major));
+                (minor(dx), minor(dy))
+            }
+        }
+    }
+}
+
+impl<'a, T> Iterator for Ray<'a, T> {
+    type Item = (Point2D, &'a T);
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        self.step += 1;
+        if Some(self.step) == self.period {
+            self.finished = true;
+            return None;
+        }
+        let (dx, dy) = self.offset(self.step);
+        match self.grid.offset(self.start, dx, dy) {
+            Some(point) => Some((point, &self.grid[point])),
+            None => {
+                self.finished = true;
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+fn points<'a, T: 'a>(ray: impl Iterator<Item = (Point2D, &'a T)>) -> Vec<Point2D> {
+    ray.map(|(point, _)| point).collect()
+}
+
+#[test]
+fn test_cast_ray() {
+    let grid = Grid::new(4, 3, (0..12).collect::<Vec<i32>>());
+    let cells: Vec<(Point2D, &i32)> = grid.cast_ray((0, 0), (1, 1)).collect();
+    assert_eq!(cells, [((1, 1), &5), ((2, 2), &10)]);
+    assert_eq!(points(grid.cast_ray((0, 0), (3, 1))), [(3, 1)]);
+    assert_eq!(points(grid.cast_ray((3, 2), (-1, -1))), [(2, 1), (1, 0)]);
+    assert_eq!(points(grid.cast_ray((1, 2), (0, -2))), [(1, 0)]);
+
+    let grid = grid.with_boundary(Boundary::WrapHorizontal);
+    assert_eq!(points(grid.cast_ray((0, 0), (3, 1))), [(3, 1), (2, 2)]);
+    assert_eq!(points(grid.cast_ray((0, 0), (-1, 1))), [(3, 1), (2, 2)]);
+
+    // rays that can't leave the grid end before they are back at the start
+    assert_eq!(
+        points(grid.cast_ray((1, 2), (1, 0))),
+        [(2, 2), (3, 2), (0, 2)]
+    );
+    assert_eq!(points(grid.cast_ray((1, 2), (-2, 0))), [(3, 2)]);
+    assert!(points(grid.cast_ray((1, 2), (4, 0))).is_empty());
+
+    let grid = grid.with_boundary(Boundary::Toroidal);
+    let diagonal = points(grid.cast_ray((0, 0), (1, 1)));
+    assert_eq!(diagonal.len(), 11);
+    assert_eq!(&diagonal[..4], [(1, 1), (2, 2), (3, 0), (0, 1)]);
+    assert_eq!(points(grid.cast_ray((0, 0), (2, 3))), [(2, 0)]);
+}
+
+#[test]
+fn test_cast_line() {
+    let grid = Grid::new(7, 5, vec![0; 35]);
+    assert_eq!(
+        points(grid.cast_line((0, 0), (3, 2))),
+        [(1, 1), (2, 1), (3, 2), (4, 3), (5, 3), (6, 4)]
+    );
+    assert_eq!(
+        points(grid.cast_line((0, 4), (1, -2))),
+        [(1, 3), (1, 2), (2, 1), (2, 0)]
+    );
+    // lattice directions visit the same cells as rays, plus the ones in between
+    assert_eq!(points(grid.cast_line((0, 0), (2, 0))).len(), 6);
+    assert_eq!(
+        points(grid.cast_line((0, 0), (1, 1))),
+        points(grid.cast_ray((0, 0), (1, 1)))
+    );
+
+    let grid = grid.with_boundary(Boundary::Toroidal);
+    let line = points(grid.cast_line((0, 0), (3, 2)));
+    assert_eq!(line.len(), 3 * 35 - 1);
+    assert_eq!(&line[..3], [(1, 1), (2, 1), (3, 2)]);
+
+    // non-reduced directions end after the same period as the reduced ones
+    let grid = Grid::new(3, 1, vec![0; 3]).with_boundary(Boundary::WrapHorizontal);
+    assert_eq!(points(grid.cast_line((0, 0), (2, 0))), [(1, 0), (2, 0)]);
+    let grid = Grid::new(3, 3, vec![0; 9]).with_boundary(Boundary::Toroidal);
+    assert_eq!(
+        points(grid.cast_line((0, 0), (2, 2))),
+        points(grid.cast_line((0, 0), (1, 1)))
+    );
+    assert_eq!(points(grid.cast_line((0, 0), (-2, 2))), [(2, 1), (1, 2)]);
+}
+
+#[test]
+#[should_panic(expected = "ray direction must not be (0, 0)")]
+fn test_cast_ray_without_direction() {
+    Grid::new(1, 1, vec![0]).cast_ray((0, 0), (0, 0));
+}